@@ -1,16 +1,102 @@
 use super::block_header::BlockHeader;
-use crate::transaction::transaction::Transaction;
+use crate::{
+    transaction::{transaction::Transaction, transaction_output::TransactionOutput},
+    utils::{
+        deserializer::{Deserializable, Deserializer},
+        hasher::Hasher,
+        serializer::{Serializable, Serializer},
+    },
+};
 
+#[derive(Clone)]
 pub struct Block {
     pub header: BlockHeader,
     pub transactions: Vec<Transaction>,
 }
 
 impl Block {
+    // Creates the first block of a chain. Its coinbase transaction creates the specified allocations out of thin air.
+    pub fn genesis(
+        version: u32,
+        timestamp: u64,
+        nonce: u32,
+        message: &str,
+        allocations: Vec<TransactionOutput>,
+    ) -> Self {
+        let coinbase_transaction = Transaction {
+            version,
+            reward: allocations.iter().map(|output| output.value).sum(),
+            inputs: vec![],
+            outputs: allocations,
+            locktime: 0,
+            coinbase_data: message.as_bytes().to_vec(),
+        };
+
+        let mut block = Self {
+            header: BlockHeader {
+                version,
+                previous_block_hash: 0x0,
+                merkle_root: 0x0,
+                timestamp,
+                difficulty_target: 0,
+                nonce,
+            },
+            transactions: vec![coinbase_transaction],
+        };
+
+        block.header.merkle_root = block.compute_merkle_root();
+        block
+    }
+
     pub fn hash(&self) -> u32 {
-        // Dummy hash function, which doesn't hash anything.
-        // It just returns the noonce.
+        self.header.hash()
+    }
+
+    // Root of the tree obtained by hashing the transaction hashes two by two (the last one is duplicated if needed)
+    pub fn compute_merkle_root(&self) -> u32 {
+        let mut hashes: Vec<u32> = self
+            .transactions
+            .iter()
+            .map(|transaction| transaction.hash())
+            .collect();
+
+        if hashes.is_empty() {
+            return 0;
+        }
+
+        while hashes.len() > 1 {
+            if hashes.len() % 2 == 1 {
+                hashes.push(*hashes.last().unwrap());
+            }
+
+            hashes = hashes
+                .chunks(2)
+                .map(|pair| {
+                    let mut hasher = Hasher::new();
+
+                    hasher.write_u32(pair[0]);
+                    hasher.write_u32(pair[1]);
+                    hasher.finish()
+                })
+                .collect();
+        }
+
+        hashes[0]
+    }
+}
+
+impl Serializable for Block {
+    fn serialize(&self, serializer: &mut Serializer) {
+        self.header.serialize(serializer);
+        serializer.write_list(&self.transactions);
+    }
+}
 
-        self.header.nonce
+impl Deserializable for Block {
+    fn deserialize(deserializer: &mut Deserializer) -> Option<Self> {
+        Some(Self {
+            header: BlockHeader::deserialize(deserializer)?,
+            transactions: deserializer.read_list()?,
+        })
     }
 }
@@ -1,23 +1,66 @@
-use crate::constants::VERSION;
+use crate::{
+    constants::TIMESTAMP_64_BIT_VERSION,
+    utils::{
+        deserializer::{Deserializable, Deserializer},
+        hasher::Hasher,
+        serializer::{Serializable, Serializer},
+    },
+};
 
+#[derive(Clone)]
 pub struct BlockHeader {
     pub version: u32,
     pub previous_block_hash: u32, // 4 bytes instead of 256 for convenience
     pub merkle_root: u32,
-    pub timestamp: u32,
+    pub timestamp: u64, // Only the lower 32 bits can be used before `TIMESTAMP_64_BIT_VERSION`
     pub difficulty_target: u32,
     pub nonce: u32,
 }
 
 impl BlockHeader {
-    pub fn genesis() -> Self {
-        Self {
-            version: VERSION,
-            previous_block_hash: 0x0,
-            merkle_root: 0x0,
-            timestamp: 0,
-            difficulty_target: 0,
-            nonce: 0,
-        }
+    // The hash covers the canonical encoding of every field, so the transactions are committed to through the merkle root
+    pub fn hash(&self) -> u32 {
+        let mut hasher = Hasher::new();
+
+        hasher.write(&self.to_bytes());
+        hasher.finish()
+    }
+
+    // Whether the hash of the header has enough leading zeroes for its difficulty
+    pub fn has_valid_proof_of_work(&self) -> bool {
+        self.hash().leading_zeros() >= self.difficulty_target
+    }
+
+    pub fn has_64_bit_timestamp(&self) -> bool {
+        self.version >= TIMESTAMP_64_BIT_VERSION
+    }
+
+    // Expected number of hashes needed to find a block with this difficulty
+    pub fn get_work(&self) -> u64 {
+        1u64.checked_shl(self.difficulty_target).unwrap_or(u64::MAX)
+    }
+}
+
+impl Serializable for BlockHeader {
+    fn serialize(&self, serializer: &mut Serializer) {
+        serializer.write_u32(self.version);
+        serializer.write_u32(self.previous_block_hash);
+        serializer.write_u32(self.merkle_root);
+        serializer.write_u64(self.timestamp);
+        serializer.write_u32(self.difficulty_target);
+        serializer.write_u32(self.nonce);
+    }
+}
+
+impl Deserializable for BlockHeader {
+    fn deserialize(deserializer: &mut Deserializer) -> Option<Self> {
+        Some(Self {
+            version: deserializer.read_u32()?,
+            previous_block_hash: deserializer.read_u32()?,
+            merkle_root: deserializer.read_u32()?,
+            timestamp: deserializer.read_u64()?,
+            difficulty_target: deserializer.read_u32()?,
+            nonce: deserializer.read_u32()?,
+        })
     }
 }
@@ -0,0 +1,22 @@
+use super::block::Block;
+
+// Block built by the node on top of its current chain, only missing a valid nonce
+#[derive(Clone)]
+pub struct BlockTemplate {
+    pub block: Block,
+    pub height: u32,
+    pub fees: u64,   // Collected from the included transactions
+    pub reward: u64, // Block value plus fees, paid by the coinbase transaction
+}
+
+impl BlockTemplate {
+    // Changes the last 4 bytes of the coinbase data (reserved for it), which gives a new merkle root and thus a new
+    // range of header hashes once all the nonces have been tried
+    pub fn set_extra_nonce(&mut self, extra_nonce: u32) {
+        let coinbase_data = &mut self.block.transactions[0].coinbase_data;
+        let extra_nonce_position = coinbase_data.len() - 4;
+
+        coinbase_data[extra_nonce_position..].copy_from_slice(&extra_nonce.to_le_bytes());
+        self.block.header.merkle_root = self.block.compute_merkle_root();
+    }
+}
@@ -0,0 +1,17 @@
+use std::fmt;
+
+// Reason why a block template can't be built
+#[derive(Debug, Clone)]
+pub enum BlockTemplateError {
+    NoPayouts, // The coinbase transaction needs at least one recipient to pay the reward to
+}
+
+impl fmt::Display for BlockTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoPayouts => write!(f, "no recipient to pay the block reward to"),
+        }
+    }
+}
+
+impl std::error::Error for BlockTemplateError {}
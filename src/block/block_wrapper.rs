@@ -1,14 +1,32 @@
 use super::block_header::BlockHeader;
+use crate::{
+    deployment::deployment_state::DeploymentState,
+    transaction::{spent_output::SpentOutput, transaction::Transaction},
+};
 
 pub struct BlockWrapper {
     pub header: BlockHeader,
+    pub transactions: Option<Vec<Transaction>>, // None until the block body has been received
+    pub has_chain_transactions: bool, // Whether the transactions of this block and all its ancestors have been received
+    pub height: u32,
+    pub chain_work: u64, // Total work of the chain ending with this block
+    pub sequence: u64,   // Order in which the block has been received (header, then transactions), to choose between chains with the same work
+    pub spent_outputs: Vec<SpentOutput>, // Filled when the block is connected to the chain
+    pub deployment_states: Vec<DeploymentState>, // State of each deployment for the blocks following this one
     pub next_blocks_hashes: Vec<u32>,
 }
 
 impl BlockWrapper {
-    pub fn from_header(header: BlockHeader) -> Self {
+    pub fn from_header(header: BlockHeader, height: u32, chain_work: u64, sequence: u64) -> Self {
         Self {
             header,
+            transactions: None,
+            has_chain_transactions: false,
+            height,
+            chain_work,
+            sequence,
+            spent_outputs: vec![],
+            deployment_states: vec![],
             next_blocks_hashes: vec![],
         }
     }
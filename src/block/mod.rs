@@ -1,3 +1,9 @@
 pub mod block;
 pub mod block_header;
-pub mod block_wrapper;
\ No newline at end of file
+pub mod block_template;
+pub mod block_template_error;
+pub mod block_wrapper;
+pub mod orphan_block_pool;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
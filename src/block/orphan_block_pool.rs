@@ -0,0 +1,100 @@
+use super::block::Block;
+use std::collections::HashMap;
+
+// Blocks whose previous block is not known yet, waiting for it to arrive.
+// When the pool is full, the oldest block is evicted.
+pub struct OrphanBlockPool {
+    max_size: usize,
+    blocks: HashMap<u32, (u64, Block)>, // Block hash -> (insertion number, block)
+    blocks_by_parent: HashMap<u32, Vec<u32>>, // Missing parent hash -> hashes of the blocks waiting for it
+    next_insertion_number: u64,
+}
+
+impl OrphanBlockPool {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            blocks: HashMap::new(),
+            blocks_by_parent: HashMap::new(),
+            next_insertion_number: 0,
+        }
+    }
+
+    pub fn contains(&self, block_hash: u32) -> bool {
+        self.blocks.contains_key(&block_hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    // Adds a block to the pool, returning the hash of the block that has been evicted to make room for it (if any)
+    pub fn insert(&mut self, block: Block) -> Option<u32> {
+        let block_hash = block.hash();
+        let mut evicted_block_hash = None;
+
+        if self.max_size == 0 || self.contains(block_hash) {
+            return None;
+        }
+
+        if self.blocks.len() >= self.max_size {
+            let oldest_block_hash = *self
+                .blocks
+                .iter()
+                .min_by_key(|(_, (insertion_number, _))| *insertion_number)
+                .unwrap()
+                .0;
+
+            self.remove(oldest_block_hash);
+            evicted_block_hash = Some(oldest_block_hash);
+        }
+
+        self.blocks_by_parent
+            .entry(block.header.previous_block_hash)
+            .or_default()
+            .push(block_hash);
+        self.blocks
+            .insert(block_hash, (self.next_insertion_number, block));
+        self.next_insertion_number += 1;
+
+        evicted_block_hash
+    }
+
+    pub fn remove(&mut self, block_hash: u32) -> Option<Block> {
+        let (_, block) = self.blocks.remove(&block_hash)?;
+        let parent_hash = block.header.previous_block_hash;
+        let siblings = self.blocks_by_parent.get_mut(&parent_hash).unwrap();
+
+        siblings.retain(|hash| *hash != block_hash);
+
+        if siblings.is_empty() {
+            self.blocks_by_parent.remove(&parent_hash);
+        }
+
+        Some(block)
+    }
+
+    // Removes and returns all the blocks waiting for the specified parent
+    pub fn remove_children(&mut self, parent_hash: u32) -> Vec<Block> {
+        let children_hashes = self
+            .blocks_by_parent
+            .remove(&parent_hash)
+            .unwrap_or_default();
+
+        children_hashes
+            .into_iter()
+            .filter_map(|hash| self.blocks.remove(&hash))
+            .map(|(_, block)| block)
+            .collect()
+    }
+
+    // Hashes of the blocks that should be requested from peers to connect the orphans.
+    // Parents that are themselves orphans are not included, since we already have them.
+    pub fn get_missing_parent_hashes(&self) -> Vec<u32> {
+        self.blocks_by_parent
+            .keys()
+            .filter(|parent_hash| !self.contains(**parent_hash))
+            .copied()
+            .collect()
+    }
+}
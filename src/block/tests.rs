@@ -0,0 +1,85 @@
+use super::{block::Block, block_header::BlockHeader};
+use crate::{
+    transaction::{transaction::Transaction, transaction_input::TransactionInput, transaction_output::TransactionOutput},
+    utils::{deserializer::Deserializable, serializer::Serializable},
+};
+
+fn build_block() -> Block {
+    Block {
+        header: BlockHeader {
+            version: 2,
+            previous_block_hash: 0x01020304,
+            merkle_root: 5,
+            timestamp: 6,
+            difficulty_target: 7,
+            nonce: 8,
+        },
+        transactions: vec![Transaction {
+            version: 1,
+            reward: 0,
+            inputs: vec![TransactionInput {
+                prev_transaction_hash: 9,
+                output_index: 10,
+                signature: 11,
+                sequence: 12,
+            }],
+            outputs: vec![TransactionOutput {
+                recipient_public_key: 13,
+                value: 14,
+            }],
+            locktime: 15,
+            coinbase_data: vec![16, 17],
+        }],
+    }
+}
+
+#[test]
+fn headers_have_a_canonical_encoding() {
+    let header = build_block().header;
+
+    assert_eq!(
+        header.to_bytes(),
+        vec![
+            2, 0, 0, 0, // version
+            4, 3, 2, 1, // previous_block_hash
+            5, 0, 0, 0, // merkle_root
+            6, 0, 0, 0, 0, 0, 0, 0, // timestamp
+            7, 0, 0, 0, // difficulty_target
+            8, 0, 0, 0, // nonce
+        ]
+    );
+}
+
+#[test]
+fn lists_and_bytes_are_prefixed_by_their_length() {
+    let transaction = build_block().transactions.remove(0);
+
+    assert_eq!(
+        transaction.to_bytes(),
+        vec![
+            1, 0, 0, 0, // version
+            0, 0, 0, 0, 0, 0, 0, 0, // reward
+            1, 0, 0, 0, // number of inputs
+            9, 0, 0, 0, 10, 0, 0, 0, 11, 0, 0, 0, 12, 0, 0, 0,
+            1, 0, 0, 0, // number of outputs
+            14, 0, 0, 0, 0, 0, 0, 0, 13, 0, 0, 0,
+            15, 0, 0, 0, // locktime
+            2, 0, 0, 0, 16, 17, // coinbase_data
+        ]
+    );
+    assert_eq!(transaction.get_size(), 58);
+}
+
+#[test]
+fn blocks_survive_a_serialization_round_trip() {
+    let block = build_block();
+    let bytes = block.to_bytes();
+    let decoded_block = Block::from_bytes(&bytes).unwrap();
+
+    assert_eq!(decoded_block.hash(), block.hash());
+    assert_eq!(decoded_block.to_bytes(), bytes);
+
+    // Truncated or trailing bytes are rejected
+    assert!(Block::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    assert!(Block::from_bytes(&[bytes.clone(), vec![0]].concat()).is_none());
+}
@@ -1,5 +1,39 @@
-pub const VERSION: u32 = 1;
-pub const MAX_AHEAD_OF_TIME_TIMESTAMP_SECS: u32 = 2 * 60 * 60; // A block can have a timestamp up to 2 hours after the node time
-pub const STARTING_DIFFICULTY: u32 = 4;
-pub const GENESIS_BLOCK_HASH: u32 = 0;
-pub const BLOCK_VALUE: u64 = 100;
+pub const TIMESTAMP_64_BIT_VERSION: u32 = 2; // Blocks with a version lower than this one must have a timestamp that fits in 32 bits
+pub const MEDIAN_TIME_SPAN: usize = 11; // Number of blocks used to compute the median time past
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000; // Below this value `locktime` is a block height, above it is a timestamp
+pub const SEQUENCE_FINAL: u32 = 0xFFFFFFFF;
+pub const RELATIVE_LOCKTIME_VERSION: u32 = 2; // Relative locktimes only apply to transactions with at least this version
+pub const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1 << 31;
+pub const SEQUENCE_LOCKTIME_TYPE_FLAG: u32 = 1 << 22; // If set, the relative locktime is time based, otherwise it's height based
+pub const SEQUENCE_LOCKTIME_MASK: u32 = 0x0000FFFF;
+pub const SEQUENCE_LOCKTIME_GRANULARITY: u32 = 9; // Time based relative locktimes are expressed in units of 512 seconds
+pub const MAX_COINBASE_DATA_SIZE: usize = 100;
+pub const MAX_ORPHAN_BLOCKS: usize = 100; // Maximum number of blocks waiting for their parent
+pub const VERSION_BITS_TOP_BITS: u32 = 0x20000000; // Versions signaling for deployments start with these bits
+pub const VERSION_BITS_TOP_MASK: u32 = 0xE0000000;
+pub const DEPLOYMENT_MERKLE_ROOT: &str = "merkle_root"; // Checks the merkle root of the blocks
+pub const DEPLOYMENT_COINBASE_HEIGHT: &str = "coinbase_height"; // Requires the coinbase data to start with the block height
+pub const DEFAULT_MEMPOOL_MAX_SIZE: usize = 300_000; // Maximum total size (in bytes) of the transactions waiting to be mined
+pub const MIN_RELAY_FEE_RATE: u64 = 10; // Fee rates are expressed in units per 1000 bytes
+pub const INCREMENTAL_RELAY_FEE_RATE: u64 = 10; // Added to the fee rate of evicted transactions to compute the minimum fee rate
+pub const ROLLING_FEE_RATE_HALF_LIFE: u64 = 12 * 60 * 60; // The minimum fee rate raised by evictions is halved every 12 hours
+pub const MAX_BIP125_RBF_SEQUENCE: u32 = 0xFFFFFFFD; // Transactions with an input sequence up to this value can be replaced in the pool
+pub const MAX_REPLACEMENT_EVICTIONS: usize = 100; // Maximum number of transactions of the pool a transaction can replace (including descendants)
+pub const MAX_ANCESTOR_COUNT: usize = 25; // Maximum number of transactions of the pool in a chain of unconfirmed transactions (including itself)
+pub const MAX_ANCESTOR_SIZE: usize = 101_000;
+pub const MAX_DESCENDANT_COUNT: usize = 25;
+pub const MAX_DESCENDANT_SIZE: usize = 101_000;
+pub const DEFAULT_MEMPOOL_EXPIRY: u64 = 14 * 24 * 60 * 60; // Transactions are removed from the pool after two weeks
+pub const MEMPOOL_FILE_NAME: &str = "mempool.dat"; // File of the data directory where the pool is saved
+pub const MEMPOOL_FILE_VERSION: u32 = 1;
+pub const MAX_ORPHAN_TRANSACTIONS: usize = 100; // Maximum number of transactions waiting for the transactions they spend
+pub const MAX_ORPHAN_TRANSACTIONS_PER_PEER: usize = 10;
+pub const MAX_ORPHAN_TRANSACTION_SIZE: usize = 100_000; // Larger transactions are not kept while waiting for their parents
+pub const MAX_PACKAGE_COUNT: usize = 25; // Maximum number of transactions accepted together in the pool
+pub const MAX_PACKAGE_SIZE: usize = 101_000;
+pub const FEE_ESTIMATOR_MAX_TARGET: u32 = 48; // Maximum number of blocks within which a confirmation fee rate can be estimated
+pub const FEE_ESTIMATOR_DECAY: f64 = 0.998; // Weight kept by past confirmations at each block (halved in about 350 blocks)
+pub const FEE_ESTIMATOR_BUCKET_SPACING: f64 = 1.1; // Each fee rate bucket starts 10% above the previous one
+pub const FEE_ESTIMATOR_MAX_FEE_RATE: u64 = 10_000_000;
+pub const FEE_ESTIMATOR_SUFFICIENT_TRANSACTIONS: f64 = 10.0; // Minimum number of transactions of a fee rate range to use it for estimates
+pub const MINER_STOP_CHECK_INTERVAL: u32 = 0x10000; // Number of nonces a mining thread tries between two checks for cancellation
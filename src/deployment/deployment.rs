@@ -0,0 +1,21 @@
+use crate::constants::{VERSION_BITS_TOP_BITS, VERSION_BITS_TOP_MASK};
+
+// New consensus rules that can be activated on a running chain, once enough miners signal for them
+// by setting the deployment bit in the version of their blocks.
+#[derive(Clone)]
+pub struct Deployment {
+    pub name: &'static str,
+    pub bit: u8,
+    pub start_time: u64, // Median time past from which blocks can signal for the deployment
+    pub timeout: u64,    // Median time past from which the deployment fails if it's not locked in
+}
+
+impl Deployment {
+    pub fn is_signaled_by(&self, version: u32) -> bool {
+        version & VERSION_BITS_TOP_MASK == VERSION_BITS_TOP_BITS && version & self.get_mask() != 0
+    }
+
+    pub fn get_mask(&self) -> u32 {
+        1 << self.bit
+    }
+}
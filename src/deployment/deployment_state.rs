@@ -0,0 +1,8 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeploymentState {
+    Defined,  // The start time has not been reached yet
+    Started,  // Miners can signal for the deployment
+    LockedIn, // Enough blocks signaled during the last window, the rules will be active after the next one
+    Active,   // The rules are enforced
+    Failed,   // The timeout has been reached before the deployment was locked in
+}
@@ -0,0 +1,13 @@
+use super::deployment_state::DeploymentState;
+
+// State of a deployment for the next block, and progress of the signaling in the current window
+#[derive(Debug, Clone)]
+pub struct DeploymentStatus {
+    pub name: &'static str,
+    pub bit: u8,
+    pub state: DeploymentState,
+    pub window_elapsed: u32, // Number of blocks of the current window already in the chain
+    pub window_signals: u32, // Number of those blocks signaling for the deployment
+    pub window: u32,
+    pub threshold: u32,
+}
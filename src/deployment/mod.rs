@@ -0,0 +1,3 @@
+pub mod deployment;
+pub mod deployment_state;
+pub mod deployment_status;
\ No newline at end of file
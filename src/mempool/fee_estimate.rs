@@ -0,0 +1,8 @@
+use super::fee_estimate_confidence::FeeEstimateConfidence;
+
+#[derive(Debug, Clone)]
+pub struct FeeEstimate {
+    pub fee_rate: u64,      // Fee per 1000 bytes
+    pub target_blocks: u32, // May be higher than the requested target if there was not enough data for it
+    pub confidence: FeeEstimateConfidence,
+}
@@ -0,0 +1,18 @@
+// How sure an estimate must be that a transaction paying the estimated fee rate confirms in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeEstimateConfidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl FeeEstimateConfidence {
+    // Share of the past transactions of a fee rate range that must have confirmed in time
+    pub fn get_success_rate(&self) -> f64 {
+        match self {
+            Self::Low => 0.6,
+            Self::Medium => 0.85,
+            Self::High => 0.95,
+        }
+    }
+}
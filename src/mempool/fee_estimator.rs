@@ -0,0 +1,169 @@
+use super::{fee_estimate::FeeEstimate, fee_estimate_confidence::FeeEstimateConfidence, mempool::Mempool};
+use crate::constants::{
+    FEE_ESTIMATOR_BUCKET_SPACING, FEE_ESTIMATOR_DECAY, FEE_ESTIMATOR_MAX_FEE_RATE, FEE_ESTIMATOR_MAX_TARGET,
+    FEE_ESTIMATOR_SUFFICIENT_TRANSACTIONS, MIN_RELAY_FEE_RATE,
+};
+use std::collections::HashMap;
+
+// Estimates the fee rate a transaction must pay to be mined within a number of blocks, by watching how many blocks
+// the transactions of the pool waited before being mined. Transactions are grouped in buckets of close fee rates,
+// and recent blocks weigh more than old ones.
+pub struct FeeEstimator {
+    bucket_fee_rates: Vec<u64>, // Lowest fee rate of each bucket
+    confirmed: Vec<Vec<f64>>,   // Target - 1 -> bucket -> transactions mined within `target` blocks
+    failed: Vec<Vec<f64>>,      // Target - 1 -> bucket -> transactions removed from the pool without being mined, after waiting at least `target` blocks
+    total: Vec<f64>,            // Bucket -> transactions mined
+    tracked_transactions: HashMap<u32, (u32, usize)>, // Transaction hash -> (height of the chain when it entered the pool, bucket)
+    best_height: u32,
+}
+
+impl FeeEstimator {
+    pub fn new() -> Self {
+        let mut bucket_fee_rates = vec![];
+        let mut fee_rate = MIN_RELAY_FEE_RATE as f64;
+
+        while fee_rate <= FEE_ESTIMATOR_MAX_FEE_RATE as f64 {
+            bucket_fee_rates.push(fee_rate as u64);
+            fee_rate *= FEE_ESTIMATOR_BUCKET_SPACING;
+        }
+
+        let bucket_count = bucket_fee_rates.len();
+
+        Self {
+            bucket_fee_rates,
+            confirmed: vec![vec![0.0; bucket_count]; FEE_ESTIMATOR_MAX_TARGET as usize],
+            failed: vec![vec![0.0; bucket_count]; FEE_ESTIMATOR_MAX_TARGET as usize],
+            total: vec![0.0; bucket_count],
+            tracked_transactions: HashMap::new(),
+            best_height: 0,
+        }
+    }
+
+    // Starts watching a transaction that just entered the pool, `height` being the height of the current chain
+    pub fn track(&mut self, transaction_hash: u32, fee_rate: u64, height: u32) {
+        let bucket = self.get_bucket(fee_rate);
+
+        self.tracked_transactions
+            .entry(transaction_hash)
+            .or_insert((height, bucket));
+    }
+
+    // Records the transactions mined by a block added to the chain, and the ones that left the pool without being mined
+    pub fn process_block(&mut self, height: u32, transaction_hashes: &[u32], mempool: &Mempool) {
+        // Blocks connected again after a reorg are not counted twice, but their transactions still stop being watched
+        let is_new_block = height > self.best_height;
+
+        if is_new_block {
+            self.best_height = height;
+
+            for counts in self.confirmed.iter_mut().chain(self.failed.iter_mut()) {
+                for count in counts.iter_mut() {
+                    *count *= FEE_ESTIMATOR_DECAY;
+                }
+            }
+
+            for count in self.total.iter_mut() {
+                *count *= FEE_ESTIMATOR_DECAY;
+            }
+        }
+
+        for transaction_hash in transaction_hashes {
+            if let Some((entry_height, bucket)) = self.tracked_transactions.remove(transaction_hash) {
+                if !is_new_block {
+                    continue;
+                }
+
+                let block_count = height.saturating_sub(entry_height).max(1);
+
+                for target in block_count..=FEE_ESTIMATOR_MAX_TARGET {
+                    self.confirmed[target as usize - 1][bucket] += 1.0;
+                }
+
+                self.total[bucket] += 1.0;
+            }
+        }
+
+        // Replaced, evicted or expired transactions
+        let removed_hashes: Vec<u32> = self
+            .tracked_transactions
+            .keys()
+            .filter(|transaction_hash| !mempool.contains(**transaction_hash))
+            .copied()
+            .collect();
+
+        for transaction_hash in removed_hashes {
+            let (entry_height, bucket) = self.tracked_transactions.remove(&transaction_hash).unwrap();
+
+            if !is_new_block {
+                continue;
+            }
+
+            let block_count = (height - 1).saturating_sub(entry_height).min(FEE_ESTIMATOR_MAX_TARGET);
+
+            for target in 1..=block_count {
+                self.failed[target as usize - 1][bucket] += 1.0;
+            }
+        }
+    }
+
+    // Lowest fee rate for a transaction to be mined within `target_blocks` blocks with the specified confidence.
+    // If there is not enough data for this target, the closest higher target with enough data is used. Targets above the
+    // maximum are lowered to it.
+    pub fn estimate_fee(&self, target_blocks: u32, confidence: FeeEstimateConfidence) -> Option<FeeEstimate> {
+        (target_blocks.clamp(1, FEE_ESTIMATOR_MAX_TARGET)..=FEE_ESTIMATOR_MAX_TARGET).find_map(|target| {
+            self.estimate_target_fee_rate(target, confidence.get_success_rate())
+                .map(|fee_rate| FeeEstimate {
+                    fee_rate,
+                    target_blocks: target,
+                    confidence,
+                })
+        })
+    }
+
+    // Buckets are scanned from the highest fee rate, grouping them until there is enough data, and the estimate is
+    // the lowest bucket of the last group in which enough transactions were mined in time.
+    fn estimate_target_fee_rate(&self, target: u32, success_rate: f64) -> Option<u64> {
+        let target_index = target as usize - 1;
+        let mut unconfirmed = vec![0.0; self.bucket_fee_rates.len()];
+
+        // Transactions still in the pool after waiting longer than the target count as failures
+        for (entry_height, bucket) in self.tracked_transactions.values() {
+            if self.best_height.saturating_sub(*entry_height) >= target {
+                unconfirmed[*bucket] += 1.0;
+            }
+        }
+
+        let mut fee_rate = None;
+        let mut confirmed = 0.0;
+        let mut total = 0.0;
+
+        for bucket in (0..self.bucket_fee_rates.len()).rev() {
+            confirmed += self.confirmed[target_index][bucket];
+            total += self.total[bucket] + self.failed[target_index][bucket] + unconfirmed[bucket];
+
+            if total >= FEE_ESTIMATOR_SUFFICIENT_TRANSACTIONS {
+                if confirmed / total < success_rate {
+                    break;
+                }
+
+                fee_rate = Some(self.bucket_fee_rates[bucket]);
+                confirmed = 0.0;
+                total = 0.0;
+            }
+        }
+
+        fee_rate
+    }
+
+    fn get_bucket(&self, fee_rate: u64) -> usize {
+        self.bucket_fee_rates
+            .partition_point(|bucket_fee_rate| *bucket_fee_rate <= fee_rate)
+            .saturating_sub(1)
+    }
+}
+
+impl Default for FeeEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
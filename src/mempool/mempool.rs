@@ -0,0 +1,419 @@
+use super::mempool_entry::MempoolEntry;
+use crate::{
+    constants::{
+        INCREMENTAL_RELAY_FEE_RATE, MEMPOOL_FILE_VERSION, MIN_RELAY_FEE_RATE,
+        ROLLING_FEE_RATE_HALF_LIFE,
+    },
+    transaction::transaction::Transaction,
+    utils::{
+        deserializer::{Deserializable, Deserializer},
+        serializer::{Serializable, Serializer},
+    },
+};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap, HashSet},
+    fs, io,
+    path::Path,
+};
+
+// Transactions waiting to be included in a block, indexed by descendant score.
+// When the total size exceeds the limit, the transactions with the lowest descendant score are evicted
+// and the minimum fee rate to enter the pool is raised above it. Transactions that stay too long in the pool expire.
+#[derive(Clone)]
+pub struct Mempool {
+    max_size: usize,
+    expiry: u64, // Duration after which a transaction is removed from the pool
+    size: usize,
+    entries: HashMap<u32, MempoolEntry>,
+    by_descendant_score: BTreeSet<(u64, u32)>, // (descendant score, transaction hash)
+    spent_outpoints: HashMap<(u32, u32), u32>, // (transaction hash, output index) -> hash of the transaction of the pool spending it
+    rolling_min_fee_rate: u64,
+    rolling_min_fee_rate_time: u64, // Last time the rolling minimum fee rate has been updated
+}
+
+impl Mempool {
+    pub fn new(max_size: usize, expiry: u64) -> Self {
+        Self {
+            max_size,
+            expiry,
+            size: 0,
+            entries: HashMap::new(),
+            by_descendant_score: BTreeSet::new(),
+            spent_outpoints: HashMap::new(),
+            rolling_min_fee_rate: 0,
+            rolling_min_fee_rate_time: 0,
+        }
+    }
+
+    pub fn contains(&self, transaction_hash: u32) -> bool {
+        self.entries.contains_key(&transaction_hash)
+    }
+
+    pub fn get(&self, transaction_hash: u32) -> Option<&MempoolEntry> {
+        self.entries.get(&transaction_hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Total size of the transactions of the pool
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_max_size(&self) -> usize {
+        self.max_size
+    }
+
+    // Changes the size limit, returning the hashes of the transactions evicted to fit it
+    pub fn set_max_size(&mut self, max_size: usize, now: u64) -> Vec<u32> {
+        self.max_size = max_size;
+        self.trim(now)
+    }
+
+    pub fn get_expiry(&self) -> u64 {
+        self.expiry
+    }
+
+    pub fn set_expiry(&mut self, expiry: u64) {
+        self.expiry = expiry;
+    }
+
+    // Removes the transactions (and their descendants) that entered the pool too long ago, returning their hashes
+    pub fn expire(&mut self, now: u64) -> Vec<u32> {
+        let expired_hashes: Vec<u32> = self
+            .entries
+            .values()
+            .filter(|entry| now.saturating_sub(entry.time) > self.expiry)
+            .map(|entry| entry.hash)
+            .collect();
+        let mut removed_hashes = vec![];
+
+        for transaction_hash in expired_hashes {
+            for entry in self.remove_with_descendants(transaction_hash) {
+                removed_hashes.push(entry.hash);
+            }
+        }
+
+        removed_hashes
+    }
+
+    // Writes the transactions of the pool and the time they entered it, so they can be loaded after a restart
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let entries: Vec<&MempoolEntry> = self.iter_by_priority().collect();
+        let mut serializer = Serializer::new();
+        // Write to a temporary file first, so a crash doesn't leave a truncated file
+        let temporary_path = path.with_extension("new");
+
+        serializer.write_u32(MEMPOOL_FILE_VERSION);
+        serializer.write_u32(entries.len() as u32);
+
+        for entry in entries {
+            entry.transaction.serialize(&mut serializer);
+            serializer.write_u64(entry.time);
+        }
+
+        fs::write(&temporary_path, serializer.into_bytes())?;
+        fs::rename(&temporary_path, path)
+    }
+
+    // Reads a file written by `save`, returning the transactions (parents before their children) and the time they entered the pool
+    pub fn read_file(path: &Path) -> io::Result<Vec<(Transaction, u64)>> {
+        let bytes = fs::read(path)?;
+        let mut deserializer = Deserializer::new(&bytes);
+        let invalid_data = || io::Error::new(io::ErrorKind::InvalidData, "invalid mempool file");
+
+        if deserializer.read_u32() != Some(MEMPOOL_FILE_VERSION) {
+            return Err(invalid_data());
+        }
+
+        let count = deserializer.read_u32().ok_or_else(invalid_data)?;
+        let mut transactions = vec![];
+
+        for _ in 0..count {
+            let transaction = Transaction::deserialize(&mut deserializer).ok_or_else(invalid_data)?;
+            let time = deserializer.read_u64().ok_or_else(invalid_data)?;
+
+            transactions.push((transaction, time));
+        }
+
+        match deserializer.is_finished() {
+            true => Ok(transactions),
+            false => Err(invalid_data()),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MempoolEntry> {
+        self.entries.values()
+    }
+
+    // Hash of the transaction of the pool spending the specified output, if any
+    pub fn get_spending_transaction_hash(&self, transaction_hash: u32, output_index: u32) -> Option<u32> {
+        self.spent_outpoints
+            .get(&(transaction_hash, output_index))
+            .copied()
+    }
+
+    // Minimum fee rate for a transaction to enter the pool. After an eviction, it decays back to the minimum relay fee rate over time.
+    pub fn get_min_fee_rate(&self, now: u64) -> u64 {
+        let elapsed_half_lives = now.saturating_sub(self.rolling_min_fee_rate_time) / ROLLING_FEE_RATE_HALF_LIFE;
+        let rolling_min_fee_rate = self
+            .rolling_min_fee_rate
+            .checked_shr(elapsed_half_lives as u32)
+            .unwrap_or(0);
+
+        rolling_min_fee_rate.max(MIN_RELAY_FEE_RATE)
+    }
+
+    // Adds a transaction to the pool, then evicts the transactions with the lowest descendant score until the pool fits its size limit.
+    // Returns the hashes of the evicted transactions, which may include the added one. `now` is the current time, which
+    // may be later than the time the transaction entered the pool (e.g. when it's loaded after a restart).
+    pub fn insert(&mut self, entry: MempoolEntry, now: u64) -> Vec<u32> {
+        let transaction_hash = entry.hash;
+
+        if self.contains(transaction_hash) {
+            return vec![];
+        }
+
+        for input in &entry.transaction.inputs {
+            self.spent_outpoints
+                .insert((input.prev_transaction_hash, input.output_index), transaction_hash);
+        }
+
+        self.size += entry.size;
+        self.by_descendant_score
+            .insert((entry.get_descendant_score(), transaction_hash));
+        self.entries.insert(transaction_hash, entry);
+
+        // The transaction may come back to the pool after its children (e.g. when its block is disconnected)
+        let mut related_hashes = vec![transaction_hash];
+
+        related_hashes.extend(self.get_ancestor_hashes(transaction_hash));
+        related_hashes.extend(self.get_descendant_hashes(transaction_hash));
+        self.update_package_totals(&related_hashes);
+
+        self.trim(now)
+    }
+
+    // Removes all the transactions from the pool, returning them in priority order (parents before their children).
+    // The minimum fee rate is kept.
+    pub fn clear(&mut self) -> Vec<MempoolEntry> {
+        let entries: Vec<MempoolEntry> = self.iter_by_priority().cloned().collect();
+
+        self.size = 0;
+        self.entries.clear();
+        self.by_descendant_score.clear();
+        self.spent_outpoints.clear();
+
+        entries
+    }
+
+    // Removes a transaction from the pool, without its descendants
+    pub fn remove(&mut self, transaction_hash: u32) -> Option<MempoolEntry> {
+        let mut related_hashes = self.get_ancestor_hashes(transaction_hash);
+
+        related_hashes.extend(self.get_descendant_hashes(transaction_hash));
+
+        let entry = self.entries.remove(&transaction_hash)?;
+
+        for input in &entry.transaction.inputs {
+            self.spent_outpoints
+                .remove(&(input.prev_transaction_hash, input.output_index));
+        }
+
+        self.size -= entry.size;
+        self.by_descendant_score
+            .remove(&(entry.get_descendant_score(), transaction_hash));
+        self.update_package_totals(&related_hashes);
+
+        Some(entry)
+    }
+
+    // Recomputes the ancestor and descendant totals of the specified transactions, and their descendant scores
+    fn update_package_totals(&mut self, transaction_hashes: &[u32]) {
+        for transaction_hash in transaction_hashes {
+            let ancestor_hashes = self.get_ancestor_hashes(*transaction_hash);
+            let descendant_hashes = self.get_descendant_hashes(*transaction_hash);
+            let sum = |hashes: &[u32]| {
+                hashes.iter().fold((0, 0), |(size, fee), hash| {
+                    let entry = self.entries.get(hash).unwrap();
+
+                    (size + entry.size, fee + entry.fee)
+                })
+            };
+            let (ancestor_size, ancestor_fee) = sum(&ancestor_hashes);
+            let (descendant_size, descendant_fee) = sum(&descendant_hashes);
+            let entry = self.entries.get_mut(transaction_hash).unwrap();
+
+            self.by_descendant_score
+                .remove(&(entry.get_descendant_score(), entry.hash));
+            entry.ancestor_count = ancestor_hashes.len() + 1;
+            entry.ancestor_size = ancestor_size + entry.size;
+            entry.ancestor_fee = ancestor_fee + entry.fee;
+            entry.descendant_count = descendant_hashes.len() + 1;
+            entry.descendant_size = descendant_size + entry.size;
+            entry.descendant_fee = descendant_fee + entry.fee;
+            self.by_descendant_score
+                .insert((entry.get_descendant_score(), entry.hash));
+        }
+    }
+
+    // Removes a transaction from the pool as well as the transactions spending its outputs, recursively
+    pub fn remove_with_descendants(&mut self, transaction_hash: u32) -> Vec<MempoolEntry> {
+        let mut transaction_hashes = vec![transaction_hash];
+
+        transaction_hashes.extend(self.get_descendant_hashes(transaction_hash));
+        transaction_hashes
+            .into_iter()
+            .filter_map(|hash| self.remove(hash))
+            .collect()
+    }
+
+    // Hashes of the transactions of the pool whose outputs are spent by a transaction of the pool, recursively
+    pub fn get_ancestor_hashes(&self, transaction_hash: u32) -> Vec<u32> {
+        match self.entries.get(&transaction_hash) {
+            Some(entry) => self.get_transaction_ancestor_hashes(&entry.transaction),
+            None => vec![],
+        }
+    }
+
+    // Hashes of the transactions of the pool whose outputs are spent by a transaction (which may not be in the pool), recursively
+    pub fn get_transaction_ancestor_hashes(&self, transaction: &Transaction) -> Vec<u32> {
+        let mut ancestor_hashes = vec![];
+        let mut visited_hashes = HashSet::new();
+        let mut transactions = vec![transaction];
+
+        while let Some(transaction) = transactions.pop() {
+            for input in &transaction.inputs {
+                let Some(parent) = self.entries.get(&input.prev_transaction_hash) else {
+                    continue;
+                };
+
+                if visited_hashes.insert(parent.hash) {
+                    ancestor_hashes.push(parent.hash);
+                    transactions.push(&parent.transaction);
+                }
+            }
+        }
+
+        ancestor_hashes
+    }
+
+    // Hashes of the transactions of the pool spending the outputs of a transaction, recursively
+    pub fn get_descendant_hashes(&self, transaction_hash: u32) -> Vec<u32> {
+        let mut descendant_hashes = vec![];
+        let mut visited_hashes = HashSet::new();
+        let mut transaction_hashes = vec![transaction_hash];
+
+        while let Some(hash) = transaction_hashes.pop() {
+            let Some(entry) = self.entries.get(&hash) else {
+                continue;
+            };
+
+            for output_index in 0..entry.transaction.outputs.len() as u32 {
+                if let Some(child_hash) = self.get_spending_transaction_hash(hash, output_index) {
+                    if visited_hashes.insert(child_hash) {
+                        descendant_hashes.push(child_hash);
+                        transaction_hashes.push(child_hash);
+                    }
+                }
+            }
+        }
+
+        descendant_hashes
+    }
+
+    // Whether a transaction of the pool can be replaced, because it or one of its ancestors in the pool signals it (BIP125)
+    pub fn is_replaceable(&self, transaction_hash: u32) -> bool {
+        let mut visited_hashes = HashSet::new();
+        let mut transaction_hashes = vec![transaction_hash];
+
+        while let Some(hash) = transaction_hashes.pop() {
+            let Some(entry) = self.entries.get(&hash) else {
+                continue;
+            };
+
+            if entry.transaction.signals_replaceability() {
+                return true;
+            }
+
+            for input in &entry.transaction.inputs {
+                if visited_hashes.insert(input.prev_transaction_hash) {
+                    transaction_hashes.push(input.prev_transaction_hash);
+                }
+            }
+        }
+
+        false
+    }
+
+    // Transactions in the order they should be included in a block. The transaction whose package (itself and its ancestors
+    // not included yet) has the highest fee rate is included with its package, so a child paying a high fee can pull its parents in.
+    pub fn iter_by_priority(&self) -> impl Iterator<Item = &MempoolEntry> {
+        let mut ordered_entries = vec![];
+        let mut included_hashes = HashSet::new();
+        // Size and fee of the package of each transaction, without the ancestors already included
+        let mut package_totals: HashMap<u32, (usize, u64)> = self
+            .entries
+            .values()
+            .map(|entry| (entry.hash, (entry.ancestor_size, entry.ancestor_fee)))
+            .collect();
+
+        while let Some((&best_hash, _)) = package_totals
+            .iter()
+            .max_by_key(|(hash, (size, fee))| (*fee * 1000 / *size as u64, Reverse(**hash)))
+        {
+            let mut package: Vec<&MempoolEntry> = self
+                .get_ancestor_hashes(best_hash)
+                .into_iter()
+                .chain([best_hash])
+                .filter(|hash| !included_hashes.contains(hash))
+                .map(|hash| self.entries.get(&hash).unwrap())
+                .collect();
+
+            // A transaction has more ancestors than any of its ancestors, so this puts parents before their children
+            package.sort_by_key(|entry| (entry.ancestor_count, entry.hash));
+
+            for entry in package {
+                included_hashes.insert(entry.hash);
+                package_totals.remove(&entry.hash);
+                ordered_entries.push(entry);
+
+                for descendant_hash in self.get_descendant_hashes(entry.hash) {
+                    if let Some((size, fee)) = package_totals.get_mut(&descendant_hash) {
+                        *size -= entry.size;
+                        *fee -= entry.fee;
+                    }
+                }
+            }
+        }
+
+        ordered_entries.into_iter()
+    }
+
+    // Evicts the transactions with the lowest descendant score (and their descendants) until the pool fits its size limit
+    fn trim(&mut self, now: u64) -> Vec<u32> {
+        let mut evicted_hashes = vec![];
+
+        while self.size > self.max_size {
+            let (descendant_score, transaction_hash) = *self.by_descendant_score.iter().next().unwrap();
+
+            self.rolling_min_fee_rate = self
+                .get_min_fee_rate(now)
+                .max(descendant_score + INCREMENTAL_RELAY_FEE_RATE);
+            self.rolling_min_fee_rate_time = now;
+
+            for entry in self.remove_with_descendants(transaction_hash) {
+                evicted_hashes.push(entry.hash);
+            }
+        }
+
+        evicted_hashes
+    }
+}
@@ -0,0 +1,9 @@
+// Outcome of the acceptance of a transaction in the pool
+#[derive(Debug, Clone)]
+pub struct MempoolAcceptance {
+    pub transaction_hash: u32,
+    pub fee: u64,
+    pub size: usize,
+    pub fee_rate: u64, // Fee per 1000 bytes
+    pub replaced_transaction_hashes: Vec<u32>, // Conflicting transactions (and their descendants) removed from the pool
+}
@@ -0,0 +1,52 @@
+use crate::{transaction::transaction::Transaction, utils::serializer::Serializable};
+
+#[derive(Clone)]
+pub struct MempoolEntry {
+    pub transaction: Transaction,
+    pub hash: u32,
+    pub fee: u64,
+    pub size: usize,   // Size of the serialized transaction
+    pub fee_rate: u64, // Fee per 1000 bytes
+    pub time: u64,     // Time at which the transaction entered the pool
+    // Totals of the transaction and its ancestors in the pool, which must all be mined before it
+    pub ancestor_count: usize,
+    pub ancestor_size: usize,
+    pub ancestor_fee: u64,
+    // Totals of the transaction and its descendants in the pool
+    pub descendant_count: usize,
+    pub descendant_size: usize,
+    pub descendant_fee: u64,
+}
+
+impl MempoolEntry {
+    pub fn new(transaction: Transaction, fee: u64, time: u64) -> Self {
+        let size = transaction.get_size();
+
+        Self {
+            hash: transaction.hash(),
+            fee,
+            size,
+            fee_rate: fee * 1000 / size as u64,
+            time,
+            transaction,
+            ancestor_count: 1,
+            ancestor_size: size,
+            ancestor_fee: fee,
+            descendant_count: 1,
+            descendant_size: size,
+            descendant_fee: fee,
+        }
+    }
+
+    // Fee rate of the transaction mined together with its ancestors, which is what a miner actually gets for including it
+    pub fn get_ancestor_fee_rate(&self) -> u64 {
+        self.ancestor_fee * 1000 / self.ancestor_size as u64
+    }
+
+    // Fee rate used to choose which transactions to evict when the pool is full. A transaction whose descendants pay
+    // for it is as valuable as its package, so evicting it (which evicts its descendants too) must be the last resort.
+    pub fn get_descendant_score(&self) -> u64 {
+        self.fee_rate
+            .max(self.descendant_fee * 1000 / self.descendant_size as u64)
+    }
+}
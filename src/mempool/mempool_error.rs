@@ -0,0 +1,152 @@
+use crate::{
+    constants::{MAX_ANCESTOR_COUNT, MAX_ANCESTOR_SIZE, MAX_PACKAGE_COUNT, MAX_PACKAGE_SIZE, MAX_REPLACEMENT_EVICTIONS},
+    transaction::transaction_error::TransactionError,
+};
+use std::fmt;
+
+// Reason why a transaction is not accepted in the transaction pool
+#[derive(Debug, Clone)]
+pub enum MempoolError {
+    AlreadyInPool { transaction_hash: u32 },
+    AlreadyConfirmed { transaction_hash: u32 }, // The transaction is part of the current chain
+    OrphanTransaction { transaction_hash: u32, prev_transaction_hash: u32 }, // A spent transaction is unknown, the transaction is kept until it arrives
+    Conflict {
+        transaction_hash: u32,
+        input_index: usize,
+        conflicting_transaction_hash: u32, // Transaction of the pool already spending the same output, which can't be replaced
+    },
+    AncestorLimitExceeded { transaction_hash: u32, count: usize, size: usize }, // Totals of the transaction and its ancestors in the pool
+    DescendantLimitExceeded { transaction_hash: u32, ancestor_hash: u32 }, // The ancestor would have too many descendants in the pool
+    TooManyReplacements { transaction_hash: u32, count: usize },
+    ReplacementFeeTooLow { transaction_hash: u32, fee: u64, min_fee: u64 }, // The replacement must pay for the replaced transactions and its own size
+    ReplacementFeeRateTooLow { transaction_hash: u32, fee_rate: u64, replaced_fee_rate: u64 },
+    NewUnconfirmedInput { transaction_hash: u32, input_index: usize }, // The replacement spends an unconfirmed output the replaced transactions didn't spend
+    FeeTooLow { transaction_hash: u32, fee_rate: u64, min_fee_rate: u64 },
+    MempoolFull { transaction_hash: u32 }, // The transaction has been evicted right after being added
+    PackageTooLarge { transaction_hash: u32, count: usize, size: usize }, // The hash is the one of the last transaction of the package
+    PackageFeeTooLow { transaction_hash: u32, fee_rate: u64, min_fee_rate: u64 },
+    PackageNotChildWithParents { transaction_hash: u32 }, // The transaction is not spent by the last transaction of the package
+    InvalidTransaction { transaction_hash: u32, error: TransactionError },
+}
+
+impl MempoolError {
+    pub fn get_transaction_hash(&self) -> u32 {
+        match self {
+            Self::AlreadyInPool { transaction_hash }
+            | Self::AlreadyConfirmed { transaction_hash }
+            | Self::OrphanTransaction { transaction_hash, .. }
+            | Self::Conflict { transaction_hash, .. }
+            | Self::AncestorLimitExceeded { transaction_hash, .. }
+            | Self::DescendantLimitExceeded { transaction_hash, .. }
+            | Self::TooManyReplacements { transaction_hash, .. }
+            | Self::ReplacementFeeTooLow { transaction_hash, .. }
+            | Self::ReplacementFeeRateTooLow { transaction_hash, .. }
+            | Self::NewUnconfirmedInput { transaction_hash, .. }
+            | Self::FeeTooLow { transaction_hash, .. }
+            | Self::MempoolFull { transaction_hash }
+            | Self::PackageTooLarge { transaction_hash, .. }
+            | Self::PackageFeeTooLow { transaction_hash, .. }
+            | Self::PackageNotChildWithParents { transaction_hash }
+            | Self::InvalidTransaction { transaction_hash, .. } => *transaction_hash,
+        }
+    }
+}
+
+impl fmt::Display for MempoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "transaction {:#010x}: ", self.get_transaction_hash())?;
+
+        match self {
+            Self::AlreadyInPool { .. } => write!(f, "already in the pool"),
+            Self::AlreadyConfirmed { .. } => write!(f, "already confirmed"),
+            Self::OrphanTransaction {
+                prev_transaction_hash,
+                ..
+            } => write!(
+                f,
+                "spent transaction {:#010x} is unknown, waiting for it",
+                prev_transaction_hash
+            ),
+            Self::Conflict {
+                input_index,
+                conflicting_transaction_hash,
+                ..
+            } => write!(
+                f,
+                "input {} is already spent by transaction {:#010x}, which is not replaceable",
+                input_index, conflicting_transaction_hash
+            ),
+            Self::AncestorLimitExceeded { count, size, .. } => write!(
+                f,
+                "would have {} ancestors ({} bytes) in the pool, above the limits of {} ancestors and {} bytes",
+                count, size, MAX_ANCESTOR_COUNT, MAX_ANCESTOR_SIZE
+            ),
+            Self::DescendantLimitExceeded { ancestor_hash, .. } => write!(
+                f,
+                "transaction {:#010x} would have too many descendants in the pool",
+                ancestor_hash
+            ),
+            Self::TooManyReplacements { count, .. } => write!(
+                f,
+                "would replace {} transactions, more than the maximum of {}",
+                count, MAX_REPLACEMENT_EVICTIONS
+            ),
+            Self::ReplacementFeeTooLow { fee, min_fee, .. } => write!(
+                f,
+                "fee {} is below the {} units required to replace the conflicting transactions",
+                fee, min_fee
+            ),
+            Self::ReplacementFeeRateTooLow {
+                fee_rate,
+                replaced_fee_rate,
+                ..
+            } => write!(
+                f,
+                "fee rate {} is not higher than the fee rate {} of a replaced transaction",
+                fee_rate, replaced_fee_rate
+            ),
+            Self::NewUnconfirmedInput { input_index, .. } => write!(
+                f,
+                "input {} spends an unconfirmed output that the replaced transactions didn't spend",
+                input_index
+            ),
+            Self::FeeTooLow {
+                fee_rate,
+                min_fee_rate,
+                ..
+            } => write!(
+                f,
+                "fee rate {} is below the minimum fee rate {}",
+                fee_rate, min_fee_rate
+            ),
+            Self::MempoolFull { .. } => write!(f, "the pool is full of transactions with a higher fee rate"),
+            Self::PackageTooLarge { count, size, .. } => write!(
+                f,
+                "package of {} transactions ({} bytes) is above the limits of {} transactions and {} bytes",
+                count, size, MAX_PACKAGE_COUNT, MAX_PACKAGE_SIZE
+            ),
+            Self::PackageFeeTooLow {
+                fee_rate,
+                min_fee_rate,
+                ..
+            } => write!(
+                f,
+                "package fee rate {} is below the minimum fee rate {}",
+                fee_rate, min_fee_rate
+            ),
+            Self::PackageNotChildWithParents { .. } => {
+                write!(f, "not spent by the last transaction of the package")
+            }
+            Self::InvalidTransaction { error, .. } => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for MempoolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidTransaction { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+pub mod fee_estimate;
+pub mod fee_estimate_confidence;
+pub mod fee_estimator;
+pub mod mempool;
+pub mod mempool_acceptance;
+pub mod mempool_entry;
+pub mod mempool_error;
+pub mod orphan_transaction;
+pub mod orphan_transaction_pool;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
@@ -0,0 +1,9 @@
+use crate::transaction::transaction::Transaction;
+
+#[derive(Clone)]
+pub struct OrphanTransaction {
+    pub transaction: Transaction,
+    pub peer_id: Option<u32>, // Peer that sent the transaction, `None` if it was submitted locally
+    pub missing_outpoints: Vec<(u32, u32)>, // Transaction hash and output index of the spent outputs that are not known yet
+    pub insertion_number: u64,
+}
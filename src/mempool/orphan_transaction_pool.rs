@@ -0,0 +1,147 @@
+use super::orphan_transaction::OrphanTransaction;
+use crate::transaction::transaction::Transaction;
+use std::collections::HashMap;
+
+// Transactions spending outputs of transactions that are not known yet, waiting for them to arrive.
+// When the pool is full, or when a peer has sent too many of them, the oldest transaction is evicted.
+pub struct OrphanTransactionPool {
+    max_size: usize,
+    max_size_per_peer: usize,
+    transactions: HashMap<u32, OrphanTransaction>,
+    transactions_by_missing_outpoint: HashMap<(u32, u32), Vec<u32>>, // Missing outpoint -> hashes of the transactions waiting for it
+    next_insertion_number: u64,
+}
+
+impl OrphanTransactionPool {
+    pub fn new(max_size: usize, max_size_per_peer: usize) -> Self {
+        Self {
+            max_size,
+            max_size_per_peer,
+            transactions: HashMap::new(),
+            transactions_by_missing_outpoint: HashMap::new(),
+            next_insertion_number: 0,
+        }
+    }
+
+    pub fn contains(&self, transaction_hash: u32) -> bool {
+        self.transactions.contains_key(&transaction_hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    // Adds a transaction to the pool, returning the hashes of the transactions that have been evicted to make room for it
+    pub fn insert(&mut self, transaction: Transaction, peer_id: Option<u32>, missing_outpoints: Vec<(u32, u32)>) -> Vec<u32> {
+        let transaction_hash = transaction.hash();
+        let mut evicted_hashes = vec![];
+
+        if self.max_size == 0 || self.contains(transaction_hash) {
+            return evicted_hashes;
+        }
+
+        if peer_id.is_some() && self.count_from_peer(peer_id) >= self.max_size_per_peer {
+            evicted_hashes.extend(self.remove_oldest(peer_id));
+        }
+
+        if self.transactions.len() >= self.max_size {
+            evicted_hashes.extend(self.remove_oldest(None));
+        }
+
+        for outpoint in &missing_outpoints {
+            self.transactions_by_missing_outpoint
+                .entry(*outpoint)
+                .or_default()
+                .push(transaction_hash);
+        }
+
+        self.transactions.insert(
+            transaction_hash,
+            OrphanTransaction {
+                transaction,
+                peer_id,
+                missing_outpoints,
+                insertion_number: self.next_insertion_number,
+            },
+        );
+        self.next_insertion_number += 1;
+
+        evicted_hashes
+    }
+
+    pub fn remove(&mut self, transaction_hash: u32) -> Option<OrphanTransaction> {
+        let orphan = self.transactions.remove(&transaction_hash)?;
+
+        for outpoint in &orphan.missing_outpoints {
+            let waiting_hashes = self.transactions_by_missing_outpoint.get_mut(outpoint).unwrap();
+
+            waiting_hashes.retain(|hash| *hash != transaction_hash);
+
+            if waiting_hashes.is_empty() {
+                self.transactions_by_missing_outpoint.remove(outpoint);
+            }
+        }
+
+        Some(orphan)
+    }
+
+    // Removes and returns the transactions waiting for an output of the specified transaction
+    pub fn remove_children(&mut self, parent_hash: u32, output_count: usize) -> Vec<OrphanTransaction> {
+        let mut children_hashes = vec![];
+
+        for output_index in 0..output_count as u32 {
+            for hash in self
+                .transactions_by_missing_outpoint
+                .get(&(parent_hash, output_index))
+                .cloned()
+                .unwrap_or_default()
+            {
+                if !children_hashes.contains(&hash) {
+                    children_hashes.push(hash);
+                }
+            }
+        }
+
+        children_hashes
+            .into_iter()
+            .filter_map(|hash| self.remove(hash))
+            .collect()
+    }
+
+    // Removes the transactions sent by a peer (e.g. when it disconnects), returning their number
+    pub fn remove_from_peer(&mut self, peer_id: u32) -> usize {
+        let transaction_hashes: Vec<u32> = self
+            .transactions
+            .values()
+            .filter(|orphan| orphan.peer_id == Some(peer_id))
+            .map(|orphan| orphan.transaction.hash())
+            .collect();
+
+        for transaction_hash in &transaction_hashes {
+            self.remove(*transaction_hash);
+        }
+
+        transaction_hashes.len()
+    }
+
+    fn count_from_peer(&self, peer_id: Option<u32>) -> usize {
+        self.transactions
+            .values()
+            .filter(|orphan| orphan.peer_id == peer_id)
+            .count()
+    }
+
+    // Removes the oldest transaction, among the ones sent by the specified peer if any
+    fn remove_oldest(&mut self, peer_id: Option<u32>) -> Option<u32> {
+        let oldest_hash = *self
+            .transactions
+            .iter()
+            .filter(|(_, orphan)| peer_id.is_none() || orphan.peer_id == peer_id)
+            .min_by_key(|(_, orphan)| orphan.insertion_number)?
+            .0;
+
+        self.remove(oldest_hash);
+
+        Some(oldest_hash)
+    }
+}
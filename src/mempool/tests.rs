@@ -0,0 +1,129 @@
+use super::{
+    fee_estimate_confidence::FeeEstimateConfidence, fee_estimator::FeeEstimator, mempool::Mempool, mempool_entry::MempoolEntry,
+};
+use crate::{
+    constants::{DEFAULT_MEMPOOL_EXPIRY, DEFAULT_MEMPOOL_MAX_SIZE, FEE_ESTIMATOR_BUCKET_SPACING, FEE_ESTIMATOR_MAX_TARGET},
+    transaction::{transaction::Transaction, transaction_input::TransactionInput, transaction_output::TransactionOutput},
+};
+
+// Distinct transactions, only their hashes matter to the fee estimator
+fn build_transactions(first_index: u32, count: u32) -> Vec<Transaction> {
+    (first_index..first_index + count)
+        .map(|index| Transaction {
+            version: 1,
+            reward: 0,
+            inputs: vec![TransactionInput {
+                prev_transaction_hash: index,
+                output_index: 0,
+                signature: 1,
+                sequence: 0,
+            }],
+            outputs: vec![TransactionOutput {
+                recipient_public_key: 2,
+                value: 1,
+            }],
+            locktime: 0,
+            coinbase_data: vec![],
+        })
+        .collect()
+}
+
+fn get_hashes(transactions: &[Transaction]) -> Vec<u32> {
+    transactions.iter().map(|transaction| transaction.hash()).collect()
+}
+
+fn build_mempool(transactions: &[Transaction]) -> Mempool {
+    let mut mempool = Mempool::new(DEFAULT_MEMPOOL_MAX_SIZE, DEFAULT_MEMPOOL_EXPIRY);
+
+    for transaction in transactions {
+        mempool.insert(MempoolEntry::new(transaction.clone(), 0, 0), 0);
+    }
+
+    mempool
+}
+
+#[test]
+fn estimates_are_the_lowest_fee_rate_of_the_bucket() {
+    let mut fee_estimator = FeeEstimator::new();
+    let transactions = build_transactions(0, 10);
+    let empty_mempool = build_mempool(&[]);
+
+    assert!(fee_estimator.estimate_fee(1, FeeEstimateConfidence::Low).is_none());
+
+    for transaction_hash in get_hashes(&transactions) {
+        fee_estimator.track(transaction_hash, 5_000, 0);
+    }
+
+    // Not enough transactions to estimate anything yet
+    assert!(fee_estimator.estimate_fee(1, FeeEstimateConfidence::Low).is_none());
+
+    fee_estimator.process_block(1, &get_hashes(&transactions), &empty_mempool);
+
+    let estimate = fee_estimator.estimate_fee(1, FeeEstimateConfidence::High).unwrap();
+
+    assert_eq!(estimate.target_blocks, 1);
+    assert_eq!(estimate.confidence, FeeEstimateConfidence::High);
+    assert!(estimate.fee_rate <= 5_000);
+    assert!(estimate.fee_rate as f64 * FEE_ESTIMATOR_BUCKET_SPACING > 5_000.0);
+
+    // The transactions are mined within any higher target too
+    let estimate = fee_estimator.estimate_fee(FEE_ESTIMATOR_MAX_TARGET + 10, FeeEstimateConfidence::High).unwrap();
+
+    assert_eq!(estimate.target_blocks, FEE_ESTIMATOR_MAX_TARGET);
+    assert!(estimate.fee_rate <= 5_000);
+}
+
+#[test]
+fn estimates_depend_on_the_confidence() {
+    let mut fee_estimator = FeeEstimator::new();
+    let high_fee_transactions = build_transactions(0, 20);
+    let low_fee_transactions = build_transactions(100, 20);
+
+    for transaction_hash in get_hashes(&high_fee_transactions) {
+        fee_estimator.track(transaction_hash, 10_000, 0);
+    }
+
+    for transaction_hash in get_hashes(&low_fee_transactions) {
+        fee_estimator.track(transaction_hash, 1_000, 0);
+    }
+
+    // All the high fee transactions are mined in the next block, but only 70% of the low fee ones
+    let mut next_block_hashes = get_hashes(&high_fee_transactions);
+
+    next_block_hashes.extend(get_hashes(&low_fee_transactions[..14]));
+    fee_estimator.process_block(1, &next_block_hashes, &build_mempool(&low_fee_transactions[14..]));
+    fee_estimator.process_block(2, &get_hashes(&low_fee_transactions[14..]), &build_mempool(&[]));
+
+    let low_estimate = fee_estimator.estimate_fee(1, FeeEstimateConfidence::Low).unwrap();
+    let high_estimate = fee_estimator.estimate_fee(1, FeeEstimateConfidence::High).unwrap();
+
+    assert!(low_estimate.fee_rate <= 1_000);
+    assert!(high_estimate.fee_rate > 1_000);
+    assert!(high_estimate.fee_rate <= 10_000);
+
+    // Within two blocks, all the low fee transactions are mined
+    assert!(fee_estimator.estimate_fee(2, FeeEstimateConfidence::High).unwrap().fee_rate <= 1_000);
+}
+
+#[test]
+fn blocks_connected_again_after_a_reorg_are_not_counted_twice() {
+    let mut fee_estimator = FeeEstimator::new();
+    let first_transactions = build_transactions(0, 20);
+    let second_transactions = build_transactions(100, 10);
+
+    for transaction_hash in get_hashes(&first_transactions).into_iter().chain(get_hashes(&second_transactions)) {
+        fee_estimator.track(transaction_hash, 5_000, 0);
+    }
+
+    fee_estimator.process_block(1, &get_hashes(&first_transactions), &build_mempool(&second_transactions));
+
+    // The second transactions are mined by a block replacing the first one at the same height. They must stop being
+    // watched, otherwise they would count as failures once they are not found in the pool anymore.
+    fee_estimator.process_block(1, &get_hashes(&second_transactions), &build_mempool(&[]));
+    fee_estimator.process_block(2, &[], &build_mempool(&[]));
+
+    let estimate = fee_estimator.estimate_fee(1, FeeEstimateConfidence::High).unwrap();
+
+    assert_eq!(estimate.target_blocks, 1);
+    assert!(estimate.fee_rate <= 5_000);
+}
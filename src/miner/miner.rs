@@ -0,0 +1,94 @@
+use super::{mining_error::MiningError, mining_job::MiningJob};
+use crate::{
+    block::{block::Block, block_template::BlockTemplate},
+    node::node::Node,
+};
+
+// Mines blocks on top of the chain of a node, paying the rewards to the payouts (see `Node::create_block_template`).
+// When the tip of the node changes, the current search is cancelled and restarted on a new template.
+pub struct Miner {
+    thread_count: usize,
+    max_nonce: u32,
+    max_extra_nonce: u32,
+    job: Option<MiningJob>,
+}
+
+impl Miner {
+    pub fn new(thread_count: usize) -> Self {
+        Self::with_nonce_limits(thread_count, u32::MAX, u32::MAX)
+    }
+
+    // Miner searching fewer nonces and extra nonces per template, giving up sooner on templates that are hard to mine
+    pub fn with_nonce_limits(thread_count: usize, max_nonce: u32, max_extra_nonce: u32) -> Self {
+        Self {
+            thread_count,
+            max_nonce,
+            max_extra_nonce,
+            job: None,
+        }
+    }
+
+    // Searches a nonce for a template, waiting until it's found
+    pub fn find_block(&self, template: BlockTemplate) -> Result<Block, MiningError> {
+        self.start_job(template).wait()
+    }
+
+    // Mines a block on top of the chain of the node and adds it to the node, returning its hash.
+    // The node can't change while it's borrowed here, so the search never has to be restarted: it ends with a block,
+    // or fails once all the nonces and extra nonces have been tried for the template.
+    pub fn mine_block(&mut self, node: &mut Node, payouts: &[(u32, u64)]) -> Result<u32, MiningError> {
+        self.update_job(node, payouts)?;
+
+        let result = self.job.as_ref().unwrap().wait();
+
+        self.job = None;
+
+        node.add_block(result?)
+            .map_err(|error| MiningError::RejectedBlock { error })
+    }
+
+    // Checks the progress of the search without waiting: starts it (or restarts it if the tip has changed) and adds
+    // the found block to the node. Returns the result of adding the block, if the search is over.
+    pub fn poll(&mut self, node: &mut Node, payouts: &[(u32, u64)]) -> Option<Result<u32, MiningError>> {
+        if let Err(error) = self.update_job(node, payouts) {
+            return Some(Err(error));
+        }
+
+        let result = self.job.as_ref().unwrap().try_get_block().transpose()?;
+
+        self.job = None;
+
+        Some(result.and_then(|block| {
+            node.add_block(block)
+                .map_err(|error| MiningError::RejectedBlock { error })
+        }))
+    }
+
+    pub fn stop(&mut self) {
+        self.job = None;
+    }
+
+    fn update_job(&mut self, node: &Node, payouts: &[(u32, u64)]) -> Result<(), MiningError> {
+        let is_stale = self
+            .job
+            .as_ref()
+            .is_some_and(|job| job.get_previous_block_hash() != node.get_last_block_hash());
+
+        if is_stale || self.job.is_none() {
+            // The previous job is cancelled when dropped
+            self.job = None;
+
+            let template = node
+                .create_block_template(payouts.to_vec())
+                .map_err(|error| MiningError::InvalidTemplate { error })?;
+
+            self.job = Some(self.start_job(template));
+        }
+
+        Ok(())
+    }
+
+    fn start_job(&self, template: BlockTemplate) -> MiningJob {
+        MiningJob::start(template, self.thread_count, self.max_nonce, self.max_extra_nonce)
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{block::block_template_error::BlockTemplateError, node::node_error::NodeError};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum MiningError {
+    InvalidTemplate { error: BlockTemplateError },
+    ExhaustedNonces { previous_block_hash: u32 }, // No valid block with any of the nonces and extra nonces of the search
+    Cancelled,
+    RejectedBlock { error: NodeError }, // The found block has not been added to the node
+}
+
+impl fmt::Display for MiningError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidTemplate { error } => write!(f, "can't build a block template: {}", error),
+            Self::ExhaustedNonces { previous_block_hash } => write!(
+                f,
+                "all the nonces have been tried without finding a block on top of {:#010x}",
+                previous_block_hash
+            ),
+            Self::Cancelled => write!(f, "mining cancelled"),
+            Self::RejectedBlock { error } => write!(f, "mined block rejected: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for MiningError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidTemplate { error } => Some(error),
+            Self::RejectedBlock { error } => Some(error),
+            Self::ExhaustedNonces { .. } | Self::Cancelled => None,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use super::mining_error::MiningError;
+use crate::{
+    block::{block::Block, block_template::BlockTemplate},
+    constants::MINER_STOP_CHECK_INTERVAL,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+// Nonce search for a block template, split between several threads. Each thread tries the nonces up to `max_nonce`
+// for its own extra nonces, up to `max_extra_nonce`. The search stops as soon as a block is found, when all the
+// nonces have been tried, or when the job is cancelled (or dropped).
+pub struct MiningJob {
+    previous_block_hash: u32,
+    stopped: Arc<AtomicBool>,
+    receiver: Receiver<Block>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl MiningJob {
+    pub fn start(template: BlockTemplate, thread_count: usize, max_nonce: u32, max_extra_nonce: u32) -> Self {
+        let previous_block_hash = template.block.header.previous_block_hash;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let thread_count = thread_count.max(1);
+        // The channel is disconnected once all the threads are done, so a search that found nothing doesn't wait forever
+        let threads = (0..thread_count)
+            .map(|thread_index| {
+                let template = template.clone();
+                let stopped = stopped.clone();
+                let sender = sender.clone();
+
+                thread::spawn(move || {
+                    let first_extra_nonce = thread_index as u32;
+                    let extra_nonce_step = thread_count as u32;
+
+                    if let Some(block) = Self::search(template, first_extra_nonce, extra_nonce_step, max_nonce, max_extra_nonce, &stopped) {
+                        stopped.store(true, Ordering::Relaxed);
+                        sender.send(block).ok();
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            previous_block_hash,
+            stopped,
+            receiver,
+            threads,
+        }
+    }
+
+    // Block the found block extends, used to detect that the search is not useful anymore
+    pub fn get_previous_block_hash(&self) -> u32 {
+        self.previous_block_hash
+    }
+
+    // Returns the found block, if any, without waiting. Fails if the search is over without a block.
+    pub fn try_get_block(&self) -> Result<Option<Block>, MiningError> {
+        match self.receiver.try_recv() {
+            Ok(block) => Ok(Some(block)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(self.get_end_error()),
+        }
+    }
+
+    // Waits for a block to be found. Fails if all the nonces have been tried, or if the job has been cancelled.
+    pub fn wait(&self) -> Result<Block, MiningError> {
+        self.receiver.recv().map_err(|_| self.get_end_error())
+    }
+
+    pub fn cancel(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    // Reason why the threads stopped without sending a block
+    fn get_end_error(&self) -> MiningError {
+        match self.stopped.load(Ordering::Relaxed) {
+            true => MiningError::Cancelled,
+            false => MiningError::ExhaustedNonces {
+                previous_block_hash: self.previous_block_hash,
+            },
+        }
+    }
+
+    fn search(
+        mut template: BlockTemplate,
+        first_extra_nonce: u32,
+        extra_nonce_step: u32,
+        max_nonce: u32,
+        max_extra_nonce: u32,
+        stopped: &AtomicBool,
+    ) -> Option<Block> {
+        let mut extra_nonce = first_extra_nonce;
+
+        while extra_nonce <= max_extra_nonce {
+            template.set_extra_nonce(extra_nonce);
+
+            let mut header = template.block.header.clone();
+
+            for nonce in 0..=max_nonce {
+                if nonce % MINER_STOP_CHECK_INTERVAL == 0 && stopped.load(Ordering::Relaxed) {
+                    return None;
+                }
+
+                header.nonce = nonce;
+
+                if header.has_valid_proof_of_work() {
+                    template.block.header = header;
+
+                    return Some(template.block);
+                }
+            }
+
+            // All the nonces have been tried for this merkle root
+            extra_nonce = extra_nonce.checked_add(extra_nonce_step)?;
+        }
+
+        None
+    }
+}
+
+impl Drop for MiningJob {
+    fn drop(&mut self) {
+        self.cancel();
+
+        for thread in self.threads.drain(..) {
+            thread.join().ok();
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod miner;
+pub mod mining_error;
+pub mod mining_job;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
@@ -0,0 +1,166 @@
+use super::{miner::Miner, mining_error::MiningError, mining_job::MiningJob};
+use crate::{
+    block::block::Block,
+    node::{chain_params::ChainParams, node::Node},
+    utils::clock::MockClock,
+};
+use std::{thread, time::Duration};
+
+// Node asking for `difficulty` leading zero bits in block hashes. Its clock is fixed, so its templates don't change.
+fn build_node(difficulty: u32) -> Node {
+    let mut params = ChainParams::regtest();
+
+    params.starting_difficulty = difficulty;
+    Node::with_clock(params, Box::new(MockClock::new(1_000)))
+}
+
+fn get_extra_nonce(block: &Block) -> u32 {
+    let coinbase_data = &block.transactions[0].coinbase_data;
+
+    u32::from_le_bytes(coinbase_data[coinbase_data.len() - 4..].try_into().unwrap())
+}
+
+#[test]
+fn nonces_are_searched_in_order() {
+    let mut node = build_node(8);
+    let template = node.create_block_template(vec![(1, 1)]).unwrap();
+    let block = Miner::new(1).find_block(template.clone()).unwrap();
+
+    assert!(block.header.has_valid_proof_of_work());
+    assert_eq!(get_extra_nonce(&block), 0);
+    assert_eq!(block.header.merkle_root, template.block.header.merkle_root);
+
+    // The first valid nonce is found
+    for nonce in 0..block.header.nonce {
+        let mut header = block.header.clone();
+
+        header.nonce = nonce;
+        assert!(!header.has_valid_proof_of_work());
+    }
+
+    assert_eq!(node.add_block(block).unwrap(), node.get_last_block_hash());
+}
+
+#[test]
+fn extra_nonces_change_once_all_the_nonces_have_been_tried() {
+    let mut node = build_node(8);
+    let template = node.create_block_template(vec![(1, 1)]).unwrap();
+    let block = Miner::with_nonce_limits(1, 3, u32::MAX).find_block(template).unwrap();
+
+    assert!(block.header.nonce <= 3);
+    assert!(get_extra_nonce(&block) > 0);
+    // The merkle root covers the new extra nonce
+    assert_eq!(block.header.merkle_root, block.compute_merkle_root());
+
+    node.add_block(block).unwrap();
+}
+
+#[test]
+fn blocks_found_by_any_thread_are_delivered() {
+    let node = build_node(0);
+    let thread_count = 4;
+
+    // Each thread tries a single extra nonce with a single nonce. The template is chosen so that only the hash of the
+    // last thread has enough leading zeroes.
+    for recipient_public_key in 1.. {
+        let mut template = node.create_block_template(vec![(recipient_public_key, 1)]).unwrap();
+
+        template.block.header.difficulty_target = 3;
+
+        let valid_extra_nonces: Vec<u32> = (0..thread_count)
+            .filter(|extra_nonce| {
+                let mut template = template.clone();
+
+                template.set_extra_nonce(*extra_nonce);
+                template.block.header.has_valid_proof_of_work()
+            })
+            .collect();
+
+        if valid_extra_nonces != [thread_count - 1] {
+            continue;
+        }
+
+        let block = Miner::with_nonce_limits(thread_count as usize, 0, thread_count - 1)
+            .find_block(template)
+            .unwrap();
+
+        assert_eq!(get_extra_nonce(&block), thread_count - 1);
+        assert!(block.header.has_valid_proof_of_work());
+        break;
+    }
+}
+
+#[test]
+fn exhausted_searches_fail_instead_of_spinning() {
+    // No hash has more than 32 leading zeroes
+    let mut node = build_node(33);
+    let genesis_block_hash = node.get_last_block_hash();
+    let mut miner = Miner::with_nonce_limits(3, 10, 5);
+
+    assert!(matches!(
+        miner.find_block(node.create_block_template(vec![(1, 1)]).unwrap()),
+        Err(MiningError::ExhaustedNonces { previous_block_hash }) if previous_block_hash == genesis_block_hash
+    ));
+    assert!(matches!(
+        miner.mine_block(&mut node, &[(1, 1)]),
+        Err(MiningError::ExhaustedNonces { .. })
+    ));
+
+    let result = loop {
+        if let Some(result) = miner.poll(&mut node, &[(1, 1)]) {
+            break result;
+        }
+
+        thread::sleep(Duration::from_millis(1));
+    };
+
+    assert!(matches!(result, Err(MiningError::ExhaustedNonces { .. })));
+    assert!(matches!(
+        miner.mine_block(&mut node, &[]),
+        Err(MiningError::InvalidTemplate { .. })
+    ));
+}
+
+#[test]
+fn cancelled_jobs_stop_their_threads() {
+    let node = build_node(33);
+    let job = MiningJob::start(node.create_block_template(vec![(1, 1)]).unwrap(), 2, u32::MAX, u32::MAX);
+
+    assert!(matches!(job.try_get_block(), Ok(None)));
+
+    job.cancel();
+
+    assert!(matches!(job.wait(), Err(MiningError::Cancelled)));
+
+    // Dropping a job waits for its threads, which would search for hours if they were not stopped
+    let job = MiningJob::start(node.create_block_template(vec![(1, 1)]).unwrap(), 2, u32::MAX, u32::MAX);
+
+    thread::sleep(Duration::from_millis(10));
+    drop(job);
+}
+
+#[test]
+fn polling_restarts_the_search_when_the_tip_changes() {
+    let mut node = build_node(16);
+    let other_block = Miner::new(2)
+        .find_block(node.create_block_template(vec![(2, 1)]).unwrap())
+        .unwrap();
+    let mut miner = Miner::new(1);
+
+    // The search starts on top of the genesis block, then another block arrives
+    assert!(miner.poll(&mut node, &[(1, 1)]).is_none());
+
+    let other_block_hash = node.add_block(other_block).unwrap();
+
+    let block_hash = loop {
+        if let Some(result) = miner.poll(&mut node, &[(1, 1)]) {
+            break result.unwrap();
+        }
+
+        thread::sleep(Duration::from_millis(1));
+    };
+
+    // A block found on top of the genesis block wouldn't have more work than the other block
+    assert_ne!(block_hash, other_block_hash);
+    assert_eq!(node.get_last_block_hash(), block_hash);
+}
@@ -0,0 +1,11 @@
+// Outcome of the validation of a block that has not been added to the chain
+#[derive(Debug, Clone)]
+pub struct BlockValidationReport {
+    pub block_hash: u32,
+    pub height: u32,
+    pub size: usize,
+    pub fees: u64,
+    pub reward: u64,                        // Block value plus the fees
+    pub created_outpoints: Vec<(u32, u32)>, // Transaction hash and output index of the outputs created by the block
+    pub spent_outpoints: Vec<(u32, u32)>,   // Transaction hash and output index of the outputs spent by the block
+}
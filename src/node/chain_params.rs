@@ -0,0 +1,88 @@
+use crate::{
+    block::block::Block,
+    constants::{DEPLOYMENT_COINBASE_HEIGHT, DEPLOYMENT_MERKLE_ROOT},
+    deployment::deployment::Deployment,
+};
+
+// Consensus parameters of a chain. Nodes built with different parameters follow different chains,
+// so several of them can run side by side in the same process.
+#[derive(Clone)]
+pub struct ChainParams {
+    pub name: &'static str,
+    pub version: u32,
+    pub max_ahead_of_time_timestamp_secs: u64, // A block can have a timestamp up to this amount of time after the node time
+    pub starting_difficulty: u32,
+    pub genesis_block: Block,
+    pub block_value: u64,
+    pub max_block_size: usize, // In bytes, according to the canonical serialization
+    pub max_block_transactions: usize,
+    pub deployment_window: u32,    // Number of blocks over which deployment signals are counted
+    pub deployment_threshold: u32, // Number of signaling blocks in a window needed to lock in a deployment
+    pub deployments: Vec<Deployment>,
+    pub checkpoints: Vec<(u32, u32)>, // (height, hash) of blocks that must be part of the chain
+    pub assume_valid_block_hash: Option<u32>, // The signatures of this block and its ancestors are not checked
+}
+
+impl ChainParams {
+    pub fn main() -> Self {
+        Self {
+            name: "main",
+            version: 1,
+            max_ahead_of_time_timestamp_secs: 2 * 60 * 60,
+            starting_difficulty: 4,
+            genesis_block: Block::genesis(1, 0, 0, "Vitecoin genesis block", vec![]),
+            block_value: 100,
+            max_block_size: 1_000_000,
+            max_block_transactions: 10_000,
+            deployment_window: 2016,
+            deployment_threshold: 1916,
+            deployments: vec![
+                Deployment {
+                    name: DEPLOYMENT_MERKLE_ROOT,
+                    bit: 0,
+                    start_time: 1_798_761_600, // 2027-01-01
+                    timeout: 1_830_297_600,    // 2028-01-01
+                },
+                Deployment {
+                    name: DEPLOYMENT_COINBASE_HEIGHT,
+                    bit: 1,
+                    start_time: 1_798_761_600,
+                    timeout: 1_830_297_600,
+                },
+            ],
+            checkpoints: vec![],
+            assume_valid_block_hash: None,
+        }
+    }
+
+    pub fn test() -> Self {
+        Self {
+            name: "test",
+            starting_difficulty: 2,
+            genesis_block: Block::genesis(1, 0, 0, "Vitecoin test network", vec![]),
+            deployment_threshold: 1512,
+            ..Self::main()
+        }
+    }
+
+    // Local chain where any nonce is valid, so blocks can be created instantly
+    pub fn regtest() -> Self {
+        Self {
+            name: "regtest",
+            starting_difficulty: 0,
+            genesis_block: Block::genesis(1, 0, 0, "Vitecoin regression test network", vec![]),
+            deployment_window: 144,
+            deployment_threshold: 108,
+            deployments: Self::main()
+                .deployments
+                .into_iter()
+                .map(|deployment| Deployment {
+                    start_time: 0,
+                    timeout: u64::MAX,
+                    ..deployment
+                })
+                .collect(),
+            ..Self::main()
+        }
+    }
+}
@@ -1,2 +1,9 @@
+pub mod block_validation_report;
+pub mod chain_params;
 pub mod node;
-pub mod node_error;
\ No newline at end of file
+pub mod node_error;
+pub mod node_error_kind;
+pub mod validation_stats;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
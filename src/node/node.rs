@@ -1,208 +1,1868 @@
-use super::node_error::NodeError;
+use super::{
+    block_validation_report::BlockValidationReport, chain_params::ChainParams, node_error::NodeError, node_error_kind::NodeErrorKind,
+    validation_stats::ValidationStats,
+};
 use crate::{
-    block::{block::Block, block_header::BlockHeader, block_wrapper::BlockWrapper},
+    block::{
+        block::Block, block_header::BlockHeader, block_template::BlockTemplate, block_template_error::BlockTemplateError,
+        block_wrapper::BlockWrapper,
+        orphan_block_pool::OrphanBlockPool,
+    },
     constants::{
-        BLOCK_VALUE, GENESIS_BLOCK_HASH, MAX_AHEAD_OF_TIME_TIMESTAMP_SECS, STARTING_DIFFICULTY,
+        DEFAULT_MEMPOOL_EXPIRY, DEFAULT_MEMPOOL_MAX_SIZE, DEPLOYMENT_COINBASE_HEIGHT, DEPLOYMENT_MERKLE_ROOT,
+        INCREMENTAL_RELAY_FEE_RATE, MAX_ANCESTOR_COUNT, MAX_ANCESTOR_SIZE, MAX_COINBASE_DATA_SIZE,
+        MAX_DESCENDANT_COUNT, MAX_DESCENDANT_SIZE, MAX_ORPHAN_BLOCKS, MAX_REPLACEMENT_EVICTIONS,
+        MAX_ORPHAN_TRANSACTIONS, MAX_ORPHAN_TRANSACTIONS_PER_PEER, MAX_ORPHAN_TRANSACTION_SIZE,
+        MAX_PACKAGE_COUNT, MAX_PACKAGE_SIZE,
+        MEMPOOL_FILE_NAME, MEDIAN_TIME_SPAN, VERSION_BITS_TOP_BITS,
+    },
+    deployment::{
+        deployment::Deployment, deployment_state::DeploymentState,
+        deployment_status::DeploymentStatus,
+    },
+    mempool::{
+        fee_estimate::FeeEstimate, fee_estimate_confidence::FeeEstimateConfidence, fee_estimator::FeeEstimator,
+        mempool::Mempool, mempool_acceptance::MempoolAcceptance, mempool_entry::MempoolEntry,
+        mempool_error::MempoolError, orphan_transaction_pool::OrphanTransactionPool,
+    },
+    utils::{
+        clock::{Clock, SystemClock},
+        serializer::Serializable,
     },
     transaction::{
-        transaction::Transaction, transaction_input::TransactionInput, transaction_output::TransactionOutput, unspent_transaction::UnspentTransaction
+        spent_output::SpentOutput, transaction::Transaction, transaction_error::TransactionError, transaction_input::TransactionInput, transaction_output::TransactionOutput, unspent_outputs_overlay::UnspentOutputsOverlay, unspent_transaction::UnspentTransaction
     },
 };
-use std::{collections::HashMap, time::SystemTime};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    io,
+    path::Path,
+};
 
-#[derive(Default)]
 pub struct Node {
+    params: ChainParams,
+    clock: Box<dyn Clock>,
     blocks: HashMap<u32, BlockWrapper>,
     unspent_transactions: HashMap<u32, UnspentTransaction>,
     current_difficulty: u32,
     last_block_hash: u32,
-    transaction_pool: HashMap<u32, Transaction>,
+    mempool: Mempool,
+    orphan_transactions: OrphanTransactionPool,
+    fee_estimator: FeeEstimator,
+    orphan_blocks: OrphanBlockPool,
+    invalid_blocks: HashMap<u32, NodeError>, // Blocks that failed validation (or descend from one), with the reason
+    validation_stats: ValidationStats,
+    max_reorg_depth: Option<u32>, // Maximum number of blocks that can be disconnected to switch to another branch
+    next_block_sequence: u64,
+    refused_reorgs: Vec<u32>, // Last blocks of the branches with more work that were refused because they fork below the finalized height
 }
 
 impl Node {
-    pub fn new() -> Self {
+    pub fn new(params: ChainParams) -> Self {
+        Self::with_clock(params, Box::new(SystemClock))
+    }
+
+    pub fn with_clock(params: ChainParams, clock: Box<dyn Clock>) -> Self {
+        let genesis_block = params.genesis_block.clone();
+        let genesis_block_hash = genesis_block.hash();
         let mut node = Self {
+            current_difficulty: params.starting_difficulty,
+            last_block_hash: genesis_block_hash,
+            params,
+            clock,
             blocks: HashMap::default(),
             unspent_transactions: HashMap::default(),
-            current_difficulty: STARTING_DIFFICULTY,
-            last_block_hash: GENESIS_BLOCK_HASH,
-            transaction_pool: HashMap::new(),
+            mempool: Mempool::new(DEFAULT_MEMPOOL_MAX_SIZE, DEFAULT_MEMPOOL_EXPIRY),
+            orphan_transactions: OrphanTransactionPool::new(MAX_ORPHAN_TRANSACTIONS, MAX_ORPHAN_TRANSACTIONS_PER_PEER),
+            fee_estimator: FeeEstimator::new(),
+            orphan_blocks: OrphanBlockPool::new(MAX_ORPHAN_BLOCKS),
+            invalid_blocks: HashMap::new(),
+            validation_stats: ValidationStats::default(),
+            max_reorg_depth: None,
+            next_block_sequence: 1,
+            refused_reorgs: vec![],
         };
-        let genesis_block_header = BlockHeader::genesis();
 
-        node.blocks.insert(
-            GENESIS_BLOCK_HASH,
-            BlockWrapper::from_header(genesis_block_header),
-        );
+        // The genesis block is not validated: its outputs (e.g. pre-mined allocations) are directly spendable
+        for transaction in &genesis_block.transactions {
+            let transaction_hash = transaction.hash();
+            let mut unspent_transaction = UnspentTransaction::new(
+                transaction_hash,
+                0,
+                genesis_block.header.timestamp,
+            );
+
+            for (output_index, output) in transaction.outputs.iter().enumerate() {
+                unspent_transaction
+                    .unspent_outputs
+                    .insert(output_index as u32, output.clone());
+            }
+
+            node.unspent_transactions
+                .insert(transaction_hash, unspent_transaction);
+        }
+
+        let genesis_block_work = genesis_block.header.get_work();
+        let mut genesis_block_wrapper =
+            BlockWrapper::from_header(genesis_block.header, 0, genesis_block_work, 0);
+
+        genesis_block_wrapper.transactions = Some(genesis_block.transactions);
+        genesis_block_wrapper.has_chain_transactions = true;
+        genesis_block_wrapper.deployment_states =
+            vec![DeploymentState::Defined; node.params.deployments.len()];
+        node.blocks.insert(genesis_block_hash, genesis_block_wrapper);
 
         node
     }
 
     pub fn add_block(&mut self, block: Block) -> Result<u32, NodeError> {
+        let block_hash = self.accept_block(block)?;
+
+        self.process_orphan_blocks(block_hash);
+
+        Ok(block_hash)
+    }
+
+    // Runs the checks of `add_block` on a block extending the current chain, without adding it or modifying the node.
+    // This allows to check a block before mining it (without checking the proof of work), or before relaying it.
+    pub fn test_block_validity(&self, block: &Block, check_proof_of_work: bool) -> Result<BlockValidationReport, NodeError> {
         let block_hash = block.hash();
-        let prev_block_wrapper = self
-            .blocks
-            .get(&block.header.previous_block_hash)
-            .ok_or(NodeError::InvalidPrevBlockHash)?;
+        let prev_block_hash = block.header.previous_block_hash;
 
-        if block.header.difficulty_target != self.current_difficulty {
-            return Err(NodeError::InvalidDifficulty);
+        if let Some(error) = self.invalid_blocks.get(&block_hash) {
+            return Err(error.clone());
         }
 
-        if !self.check_hash_difficulty(block_hash) {
-            return Err(NodeError::InvalidDifficulty);
-        }
+        let is_header_known = match self.blocks.get(&block_hash) {
+            Some(block_wrapper) if block_wrapper.transactions.is_some() => {
+                return Err(NodeError::DuplicateBlock { block_hash });
+            }
+            Some(_) => true,
+            None => false,
+        };
 
-        if block.header.timestamp <= prev_block_wrapper.header.timestamp {
-            return Err(NodeError::InvalidTimestamp);
+        if self.orphan_blocks.contains(block_hash) {
+            return Err(NodeError::DuplicateBlock { block_hash });
         }
 
-        if block.header.timestamp > self.get_current_time() + MAX_AHEAD_OF_TIME_TIMESTAMP_SECS {
-            return Err(NodeError::InvalidTimestamp);
+        if let Some(error) = self.get_invalid_ancestor_error(&block.header) {
+            return Err(error);
         }
 
-        // TODO: check for validity of merkle root
+        if !self.blocks.contains_key(&prev_block_hash) {
+            return Err(NodeError::OrphanHeader {
+                block_hash,
+                prev_block_hash,
+            });
+        }
 
-        let mut anounced_reward = 0;
-        let mut actual_reward = BLOCK_VALUE;
-        let mut outputs_to_add: Vec<(u32, u32, TransactionOutput)> = vec![];
-        let mut inputs_to_remove: Vec<&TransactionInput> = vec![];
+        if prev_block_hash != self.last_block_hash {
+            return Err(NodeError::NotExtendingTip {
+                block_hash,
+                prev_block_hash,
+            });
+        }
 
-        if block.transactions.len() < 1 {
-            // Coinbase transaction is missing
-            return Err(NodeError::InvalidCoinbaseTransaction);
+        // A known header has already been checked, and checking it again may fail (e.g. if it's a checkpoint)
+        if !is_header_known {
+            self.check_block_header(&block.header, check_proof_of_work)?;
         }
 
-        for (index, transaction) in block.transactions.iter().enumerate() {
-            let is_coinbase_transaction = index == 0;
-            let mut input_sum = 0;
-            let mut output_sum = 0;
-            let transaction_hash = transaction.hash();
+        self.check_block_body(block)?;
 
-            if is_coinbase_transaction {
-                input_sum += transaction.reward;
-                anounced_reward = transaction.reward;
-            } else if transaction.reward != 0 {
-                return Err(NodeError::InvalidTransactionReward);
+        let (report, _) = self.check_block_transactions(block, false)?;
+
+        Ok(report)
+    }
+
+    // Validates a block header and adds it to the block tree without its transactions.
+    // This allows to find and verify the best chain of headers before downloading the blocks.
+    pub fn accept_header(&mut self, header: &BlockHeader) -> Result<u32, NodeError> {
+        let block_hash = self.index_header(header)?;
+
+        self.process_orphan_blocks(block_hash);
+
+        Ok(block_hash)
+    }
+
+    // Last block of the valid chain of headers with the most work, whose transactions may not have been received yet
+    pub fn get_best_header_hash(&self) -> u32 {
+        *self
+            .blocks
+            .iter()
+            .filter(|(hash, _)| !self.invalid_blocks.contains_key(hash))
+            .max_by_key(|(_, block_wrapper)| {
+                (block_wrapper.chain_work, Reverse(block_wrapper.sequence))
+            })
+            .unwrap()
+            .0
+    }
+
+    // Hashes of the blocks of the best chain of headers whose transactions have not been received yet, from the lowest to the highest
+    pub fn get_blocks_to_download(&self) -> Vec<u32> {
+        let mut block_hashes = vec![];
+        let mut block_hash = self.get_best_header_hash();
+        let mut block_wrapper = self.blocks.get(&block_hash).unwrap();
+
+        while !block_wrapper.has_chain_transactions {
+            if block_wrapper.transactions.is_none() {
+                block_hashes.push(block_hash);
             }
 
-            for input in &transaction.inputs {
-                let prev_transaction = self
-                    .unspent_transactions
-                    .get(&input.prev_transaction_hash)
-                    .ok_or(NodeError::InvalidTransactionInputHash)?;
+            block_hash = block_wrapper.header.previous_block_hash;
+            block_wrapper = self.blocks.get(&block_hash).unwrap();
+        }
 
-                let prev_output = prev_transaction
-                    .unspent_outputs
-                    .get(&input.output_index)
-                    .ok_or(NodeError::InvalidTransactionInputIndex)?;
+        block_hashes.reverse();
+        block_hashes
+    }
 
-                // In reality this check would be much more complex and involve cryptography
-                if input.signature != prev_output.recipient_public_key {
-                    return Err(NodeError::InvalidTransactionInputSignature);
-                }
+    // Hashes of the blocks that should be requested from peers so the orphan blocks can be connected
+    pub fn get_missing_block_hashes(&self) -> Vec<u32> {
+        self.orphan_blocks.get_missing_parent_hashes()
+    }
+
+    pub fn get_invalid_blocks(&self) -> &HashMap<u32, NodeError> {
+        &self.invalid_blocks
+    }
+
+    pub fn get_validation_stats(&self) -> &ValidationStats {
+        &self.validation_stats
+    }
+
+    pub fn set_max_reorg_depth(&mut self, max_reorg_depth: Option<u32>) {
+        self.max_reorg_depth = max_reorg_depth;
+    }
+
+    // Should be watched by the node operator: refusing a branch with more work means the node may be following a different chain than its peers
+    pub fn get_refused_reorgs(&self) -> &[u32] {
+        &self.refused_reorgs
+    }
+
+    // Height up to which the blocks of the current chain can't be replaced by another branch
+    pub fn get_finalized_height(&self) -> u32 {
+        let last_block_height = self.blocks.get(&self.last_block_hash).unwrap().height;
+        let finalized_height = match self.max_reorg_depth {
+            Some(max_reorg_depth) => last_block_height.saturating_sub(max_reorg_depth),
+            None => 0,
+        };
+        // Checkpoints that are part of the current chain can't be replaced either
+        let checkpoint_height = self
+            .params
+            .checkpoints
+            .iter()
+            .filter(|(height, hash)| {
+                *height <= last_block_height
+                    && self.get_ancestor_hash(self.last_block_hash, *height) == *hash
+            })
+            .map(|(height, _)| *height)
+            .max()
+            .unwrap_or(0);
+
+        finalized_height.max(checkpoint_height)
+    }
+
+    // Marks a block (and all its descendants) as invalid, switching to another chain if it was part of the current one
+    pub fn invalidate_block(&mut self, block_hash: u32) -> Result<(), NodeError> {
+        let block_wrapper = self
+            .blocks
+            .get(&block_hash)
+            .ok_or(NodeError::UnknownBlock { block_hash })?;
+
+        let height = block_wrapper.height;
+
+        if height == 0 {
+            return Err(NodeError::CannotInvalidateGenesisBlock { block_hash });
+        }
+
+        let finalized_height = self.get_finalized_height();
+
+        // Disconnecting the block would also disconnect finalized blocks
+        if height <= finalized_height && self.get_ancestor_hash(self.last_block_hash, height) == block_hash {
+            return Err(NodeError::ReorgTooDeep {
+                block_hash,
+                fork_height: height - 1,
+                finalized_height,
+            });
+        }
+
+        self.mark_block_invalid(block_hash, NodeError::ManuallyInvalidated { block_hash });
+        self.activate_best_chain();
+
+        Ok(())
+    }
+
+    // Removes the invalid mark of a block, its descendants and its ancestors, switching back to its chain if it's the best one.
+    // If the block is actually invalid, it will be marked again when being connected.
+    pub fn reconsider_block(&mut self, block_hash: u32) -> Result<(), NodeError> {
+        if !self.blocks.contains_key(&block_hash) {
+            return match self.invalid_blocks.remove(&block_hash) {
+                Some(_) => Ok(()),
+                None => Err(NodeError::UnknownBlock { block_hash }),
+            };
+        }
+
+        let mut descendant_hashes = vec![block_hash];
+
+        while let Some(hash) = descendant_hashes.pop() {
+            self.invalid_blocks.remove(&hash);
+            descendant_hashes.extend(&self.blocks.get(&hash).unwrap().next_blocks_hashes);
+        }
+
+        let mut ancestor_wrapper = self.blocks.get(&block_hash).unwrap();
+
+        while ancestor_wrapper.height > 0 {
+            let ancestor_hash = ancestor_wrapper.header.previous_block_hash;
+
+            self.invalid_blocks.remove(&ancestor_hash);
+            ancestor_wrapper = self.blocks.get(&ancestor_hash).unwrap();
+        }
+
+        self.activate_best_chain();
+
+        Ok(())
+    }
 
-                input_sum += prev_output.value;
+    // Connects the orphans that were waiting for this block, and recursively their own orphans
+    fn process_orphan_blocks(&mut self, block_hash: u32) {
+        let mut parent_hashes = vec![block_hash];
 
-                inputs_to_remove.push(&input);
+        while let Some(parent_hash) = parent_hashes.pop() {
+            for orphan_block in self.orphan_blocks.remove_children(parent_hash) {
+                if let Ok(orphan_block_hash) = self.accept_block(orphan_block) {
+                    parent_hashes.push(orphan_block_hash);
+                }
             }
+        }
+    }
 
-            for (output_index, output) in transaction.outputs.iter().enumerate() {
-                output_sum += output.value;
+    // Stores the block (checking its header if it was not known yet), then switches to the chain with the most work.
+    // Returns an error if the block itself turned out to be invalid.
+    fn accept_block(&mut self, block: Block) -> Result<u32, NodeError> {
+        let block_hash = block.hash();
+
+        if let Some(error) = self.invalid_blocks.get(&block_hash) {
+            return Err(error.clone());
+        }
 
-                outputs_to_add.push((transaction_hash, output_index as u32, output.clone()));
+        if let Some(block_wrapper) = self.blocks.get(&block_hash) {
+            if block_wrapper.transactions.is_some() {
+                return Err(NodeError::DuplicateBlock { block_hash });
+            }
+        } else if self.orphan_blocks.contains(block_hash) {
+            return Err(NodeError::DuplicateBlock { block_hash });
+        } else if !self.blocks.contains_key(&block.header.previous_block_hash)
+            && !self.invalid_blocks.contains_key(&block.header.previous_block_hash)
+        {
+            // Only check the proof of work, so peers can't fill the pool with blocks that cost nothing to create
+            if !self.check_hash_difficulty(block_hash) {
+                return Err(NodeError::InvalidDifficulty { block_hash });
             }
 
-            if output_sum > input_sum {
-                return Err(NodeError::InvalidTransactionBalance);
+            let prev_block_hash = block.header.previous_block_hash;
+
+            self.orphan_blocks.insert(block);
+
+            return Err(NodeError::OrphanBlock {
+                block_hash,
+                prev_block_hash,
+            });
+        }
+
+        self.index_header(&block.header)?;
+
+        if let Err(error) = self.check_block_body(&block) {
+            // The transactions don't match the header, so the header itself may still be valid
+            if !matches!(error, NodeError::InvalidMerkleRoot { .. }) {
+                self.mark_block_invalid(block_hash, error.clone());
             }
 
-            let fee = input_sum - output_sum;
+            return Err(error);
+        }
 
-            actual_reward += fee;
+        let block_wrapper = self.blocks.get_mut(&block_hash).unwrap();
+        let prev_block_hash = block_wrapper.header.previous_block_hash;
 
-            if is_coinbase_transaction && fee != 0 {
-                // The coinbase transaction should not have any fee
-                return Err(NodeError::InvalidCoinbaseTransaction);
+        block_wrapper.transactions = Some(block.transactions);
+        // Between chains with the same work, the one whose transactions have been received first is preferred
+        block_wrapper.sequence = self.next_block_sequence;
+        self.next_block_sequence += 1;
+
+        // If all the previous blocks have been received, this block and its descendants waiting for it become candidates for the best chain
+        if self.blocks.get(&prev_block_hash).unwrap().has_chain_transactions {
+            let mut block_hashes = vec![block_hash];
+
+            while let Some(hash) = block_hashes.pop() {
+                let block_wrapper = self.blocks.get_mut(&hash).unwrap();
+
+                block_wrapper.has_chain_transactions = true;
+
+                for child_hash in block_wrapper.next_blocks_hashes.clone() {
+                    if self.blocks.get(&child_hash).unwrap().transactions.is_some() {
+                        block_hashes.push(child_hash);
+                    }
+                }
             }
         }
 
-        if actual_reward != anounced_reward {
-            // The coinbase transaction does not match the block's content
-            return Err(NodeError::InvalidCoinbaseTransaction);
+        self.activate_best_chain();
+
+        match self.invalid_blocks.get(&block_hash) {
+            Some(error) => Err(error.clone()),
+            None => Ok(block_hash),
         }
+    }
 
-        // At this point the block is valid
+    // Error of a block whose previous block is invalid, referring to the block that is actually invalid
+    fn get_invalid_ancestor_error(&self, header: &BlockHeader) -> Option<NodeError> {
+        let prev_block_error = self.invalid_blocks.get(&header.previous_block_hash)?;
 
-        // Remove spent transactions
-        for input in inputs_to_remove {
-            let transaction = self
-                .unspent_transactions
-                .get_mut(&input.prev_transaction_hash)
-                .unwrap();
+        Some(NodeError::InvalidAncestor {
+            block_hash: header.hash(),
+            ancestor_hash: match prev_block_error {
+                NodeError::InvalidAncestor { ancestor_hash, .. } => *ancestor_hash,
+                _ => header.previous_block_hash,
+            },
+            ancestor_kind: prev_block_error.kind(),
+        })
+    }
 
-            transaction.unspent_outputs.remove(&input.output_index);
+    // Checks a block header and stores it. Accepting a header that is already known does nothing.
+    fn index_header(&mut self, header: &BlockHeader) -> Result<u32, NodeError> {
+        let block_hash = header.hash();
 
-            if transaction.unspent_outputs.is_empty() {
-                self.unspent_transactions
-                    .remove(&input.prev_transaction_hash);
-            }
+        if let Some(error) = self.invalid_blocks.get(&block_hash) {
+            // Don't validate the same block again
+            return Err(error.clone());
         }
 
-        // Add new unspent transactions
-        for (transaction_hash, output_index, output) in outputs_to_add {
-            let transaction = self
-                .unspent_transactions
-                .entry(transaction_hash)
-                .or_insert_with(|| UnspentTransaction::new(transaction_hash));
+        if self.blocks.contains_key(&block_hash) {
+            return Ok(block_hash);
+        }
 
-            transaction.unspent_outputs.insert(output_index, output);
-            self.transaction_pool.remove(&transaction_hash);
+        if let Some(error) = self.get_invalid_ancestor_error(header) {
+            self.mark_block_invalid(block_hash, error.clone());
+
+            return Err(error);
         }
 
-        // Register the new block
-        self.blocks
-            .insert(block_hash, BlockWrapper::from_header(block.header));
+        if !self.blocks.contains_key(&header.previous_block_hash) {
+            return Err(NodeError::OrphanHeader {
+                block_hash,
+                prev_block_hash: header.previous_block_hash,
+            });
+        }
+
+        if let Err(error) = self.check_block_header(header, true) {
+            // A block too far in the future may become valid later, so it is not remembered
+            if error.kind() != NodeErrorKind::TemporarilyUnavailable {
+                self.mark_block_invalid(block_hash, error.clone());
+            }
+
+            return Err(error);
+        }
+
+        let prev_block_wrapper = self.blocks.get(&header.previous_block_hash).unwrap();
+        let height = prev_block_wrapper.height + 1;
+        let chain_work = prev_block_wrapper.chain_work + header.get_work();
+        let sequence = self.next_block_sequence;
+
+        self.next_block_sequence += 1;
 
         // Specify that the new block is the successor of the previous one. If there was already one, this creates a new "branch" in the chain.
         self.blocks
-            .get_mut(&block_hash)
+            .get_mut(&header.previous_block_hash)
             .unwrap()
             .next_blocks_hashes
             .push(block_hash);
 
-        self.last_block_hash = block_hash;
+        // Register the new block
+        self.blocks.insert(
+            block_hash,
+            BlockWrapper::from_header(header.clone(), height, chain_work, sequence),
+        );
 
-        // TODO: adjust block difficulty
+        let deployment_states = self.compute_deployment_states(block_hash);
+
+        self.blocks.get_mut(&block_hash).unwrap().deployment_states = deployment_states;
 
         Ok(block_hash)
     }
 
-    pub fn get_last_block_hash(&self) -> u32 {
-        self.last_block_hash
+    // State of each deployment for the next block, along with the signaling progress in the current window
+    pub fn deployment_status(&self) -> Vec<DeploymentStatus> {
+        let last_block_wrapper = self.blocks.get(&self.last_block_hash).unwrap();
+        let window = self.params.deployment_window;
+        let window_elapsed = (last_block_wrapper.height + 1) % window;
+
+        self.params
+            .deployments
+            .iter()
+            .zip(&last_block_wrapper.deployment_states)
+            .map(|(deployment, state)| DeploymentStatus {
+                name: deployment.name,
+                bit: deployment.bit,
+                state: *state,
+                window_elapsed,
+                window_signals: self.count_deployment_signals(
+                    self.last_block_hash,
+                    deployment,
+                    window_elapsed,
+                ),
+                window,
+                threshold: self.params.deployment_threshold,
+            })
+            .collect()
     }
 
-    pub fn get_awaiting_transactions(&self) -> Vec<Transaction> {
-        Vec::from_iter(self.transaction_pool.values().into_iter().cloned())
+    // Builds a block on top of the current chain, including the transactions of the pool that pay the most (with their
+    // parents) as long as they fit in the block. The nonce of the header is left for the miner to find.
+    // The coinbase transaction collects the block value and the fees, split between the recipients of the payouts in
+    // proportion to their weights (e.g. weights of 1 and 3 get a quarter and three quarters of the reward, a single
+    // recipient gets everything whatever its weight). If all the weights are 0, the last recipient gets everything.
+    pub fn create_block_template(&self, payouts: Vec<(u32, u64)>) -> Result<BlockTemplate, BlockTemplateError> {
+        if payouts.is_empty() {
+            return Err(BlockTemplateError::NoPayouts);
+        }
+
+        let prev_block_wrapper = self.blocks.get(&self.last_block_hash).unwrap();
+        let height = prev_block_wrapper.height + 1;
+        let coinbase_transaction = Transaction {
+            version: self.params.version,
+            reward: 0,
+            inputs: vec![],
+            outputs: payouts
+                .iter()
+                .map(|(recipient_public_key, _)| TransactionOutput {
+                    recipient_public_key: *recipient_public_key,
+                    value: 0,
+                })
+                .collect(),
+            locktime: 0,
+            // The height is followed by the extra nonce changed by the miner
+            coinbase_data: [height.to_le_bytes(), 0u32.to_le_bytes()].concat(),
+        };
+        let mut block = Block {
+            header: BlockHeader {
+                version: self.get_next_block_version(),
+                previous_block_hash: self.last_block_hash,
+                merkle_root: 0,
+                timestamp: self.get_current_time().max(prev_block_wrapper.header.timestamp + 1),
+                difficulty_target: self.current_difficulty,
+                nonce: 0,
+            },
+            transactions: vec![coinbase_transaction],
+        };
+        // The values of the coinbase outputs don't change the size of the block
+        let mut size = block.get_size();
+        let mut fees = 0;
+        let mut skipped_hashes = HashSet::new();
+
+        for entry in self.mempool.iter_by_priority() {
+            // Children of skipped transactions can't be included without them
+            let spends_skipped_transaction = entry
+                .transaction
+                .inputs
+                .iter()
+                .any(|input| skipped_hashes.contains(&input.prev_transaction_hash));
+
+            if spends_skipped_transaction
+                || size + entry.size > self.params.max_block_size
+                || block.transactions.len() >= self.params.max_block_transactions
+            {
+                skipped_hashes.insert(entry.hash);
+                continue;
+            }
+
+            size += entry.size;
+            fees += entry.fee;
+            block.transactions.push(entry.transaction.clone());
+        }
+
+        let reward = self.params.block_value + fees;
+        let coinbase_transaction = &mut block.transactions[0];
+        let total_weight: u128 = payouts.iter().map(|(_, weight)| *weight as u128).sum();
+        let mut remaining_reward = reward;
+
+        for (output, (_, weight)) in coinbase_transaction.outputs.iter_mut().zip(&payouts) {
+            output.value = match total_weight {
+                0 => 0,
+                _ => (*weight as u128 * reward as u128 / total_weight) as u64,
+            };
+            remaining_reward -= output.value;
+        }
+
+        // What is lost when rounding goes to the last output
+        coinbase_transaction.outputs.last_mut().unwrap().value += remaining_reward;
+        coinbase_transaction.reward = reward;
+        block.header.merkle_root = block.compute_merkle_root();
+
+        Ok(BlockTemplate {
+            block,
+            height,
+            fees,
+            reward,
+        })
     }
 
-    pub fn add_transaction(&mut self, transaction: Transaction) -> u32 {
-        let hash = transaction.hash();
+    // Version to use for the next block, signaling for all the deployments that can be signaled for
+    pub fn get_next_block_version(&self) -> u32 {
+        let last_block_wrapper = self.blocks.get(&self.last_block_hash).unwrap();
+        let mut version = VERSION_BITS_TOP_BITS;
 
-        self.transaction_pool.insert(transaction.hash(), transaction);
+        for (deployment, state) in self
+            .params
+            .deployments
+            .iter()
+            .zip(&last_block_wrapper.deployment_states)
+        {
+            if matches!(state, DeploymentState::Started | DeploymentState::LockedIn) {
+                version |= deployment.get_mask();
+            }
+        }
 
-        hash
+        version
     }
 
-    fn check_hash_difficulty(&self, hash: u32) -> bool {
-        hash.leading_zeros() >= self.current_difficulty
+    // The state of the deployments only changes at the end of each window
+    fn compute_deployment_states(&self, block_hash: u32) -> Vec<DeploymentState> {
+        let block_wrapper = self.blocks.get(&block_hash).unwrap();
+        let prev_block_wrapper = self
+            .blocks
+            .get(&block_wrapper.header.previous_block_hash)
+            .unwrap();
+
+        if !(block_wrapper.height + 1).is_multiple_of(self.params.deployment_window) {
+            return prev_block_wrapper.deployment_states.clone();
+        }
+
+        let median_time_past = self.get_median_time_past(block_hash);
+
+        self.params
+            .deployments
+            .iter()
+            .zip(&prev_block_wrapper.deployment_states)
+            .map(|(deployment, state)| match state {
+                DeploymentState::Defined | DeploymentState::Started
+                    if median_time_past >= deployment.timeout =>
+                {
+                    DeploymentState::Failed
+                }
+                DeploymentState::Defined if median_time_past >= deployment.start_time => {
+                    DeploymentState::Started
+                }
+                DeploymentState::Started
+                    if self.count_deployment_signals(
+                        block_hash,
+                        deployment,
+                        self.params.deployment_window,
+                    ) >= self.params.deployment_threshold =>
+                {
+                    DeploymentState::LockedIn
+                }
+                DeploymentState::LockedIn => DeploymentState::Active,
+                state => *state,
+            })
+            .collect()
     }
 
-    fn get_current_time(&self) -> u32 {
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as u32
+    // Number of blocks signaling for the deployment among the `count` last blocks up to the specified one
+    fn count_deployment_signals(&self, block_hash: u32, deployment: &Deployment, count: u32) -> u32 {
+        let mut signals = 0;
+        let mut block_wrapper = self.blocks.get(&block_hash).unwrap();
+
+        for counted in 1..=count {
+            if deployment.is_signaled_by(block_wrapper.header.version) {
+                signals += 1;
+            }
+
+            // The genesis block has no parent
+            if counted == count || block_wrapper.height == 0 {
+                break;
+            }
+
+            block_wrapper = self
+                .blocks
+                .get(&block_wrapper.header.previous_block_hash)
+                .unwrap();
+        }
+
+        signals
+    }
+
+    fn is_deployment_active(&self, prev_block_hash: u32, name: &str) -> bool {
+        let prev_block_wrapper = self.blocks.get(&prev_block_hash).unwrap();
+
+        self.params
+            .deployments
+            .iter()
+            .zip(&prev_block_wrapper.deployment_states)
+            .any(|(deployment, state)| {
+                deployment.name == name && *state == DeploymentState::Active
+            })
+    }
+
+    fn check_block_header(&self, header: &BlockHeader, check_proof_of_work: bool) -> Result<(), NodeError> {
+        let prev_block_wrapper = self
+            .blocks
+            .get(&header.previous_block_hash)
+            .unwrap();
+
+        let block_hash = header.hash();
+        let height = prev_block_wrapper.height + 1;
+
+        if let Some((_, checkpoint_hash)) = self
+            .params
+            .checkpoints
+            .iter()
+            .find(|(checkpoint_height, _)| *checkpoint_height == height)
+        {
+            if *checkpoint_hash != block_hash {
+                return Err(NodeError::CheckpointMismatch { block_hash, height });
+            }
+        }
+
+        // All the ancestors of a known checkpoint are known, so a new block below it is necessarily on another branch
+        if height <= self.get_last_checkpoint_height() {
+            return Err(NodeError::ForkBeforeCheckpoint { block_hash, height });
+        }
+
+        let fork_block_hash = self.find_fork_block_hash(self.last_block_hash, header.previous_block_hash);
+        let fork_height = self.blocks.get(&fork_block_hash).unwrap().height;
+
+        let finalized_height = self.get_finalized_height();
+
+        if fork_height < finalized_height {
+            return Err(NodeError::ReorgTooDeep {
+                block_hash,
+                fork_height,
+                finalized_height,
+            });
+        }
+
+        if header.difficulty_target != self.current_difficulty {
+            return Err(NodeError::InvalidDifficulty { block_hash });
+        }
+
+        if check_proof_of_work && !self.check_hash_difficulty(block_hash) {
+            return Err(NodeError::InvalidDifficulty { block_hash });
+        }
+
+        let timestamp = header.timestamp;
+
+        if timestamp <= prev_block_wrapper.header.timestamp {
+            return Err(NodeError::InvalidTimestamp { block_hash, timestamp });
+        }
+
+        if timestamp > self.get_current_time() + self.params.max_ahead_of_time_timestamp_secs {
+            return Err(NodeError::FutureTimestamp { block_hash, timestamp });
+        }
+
+        if !header.has_64_bit_timestamp() && timestamp > u32::MAX as u64 {
+            // Past 2106, blocks must opt in to 64-bit timestamps by bumping their version
+            return Err(NodeError::InvalidTimestamp { block_hash, timestamp });
+        }
+
+        Ok(())
+    }
+
+    // Height of the highest checkpoint that has been received
+    fn get_last_checkpoint_height(&self) -> u32 {
+        self.params
+            .checkpoints
+            .iter()
+            .filter(|(_, hash)| self.blocks.contains_key(hash))
+            .map(|(height, _)| *height)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // A block is assumed valid if it is an ancestor of the assume-valid block, which is itself part of the best chain of headers
+    fn is_assumed_valid(&self, block_hash: u32, best_header_hash: u32) -> bool {
+        let Some(assume_valid_block_hash) = self.params.assume_valid_block_hash else {
+            return false;
+        };
+        let Some(assume_valid_block_wrapper) = self.blocks.get(&assume_valid_block_hash) else {
+            return false;
+        };
+        let block_wrapper = self.blocks.get(&block_hash).unwrap();
+
+        block_wrapper.height <= assume_valid_block_wrapper.height
+            && self.get_ancestor_hash(assume_valid_block_hash, block_wrapper.height) == block_hash
+            && self.get_ancestor_hash(best_header_hash, assume_valid_block_wrapper.height)
+                == assume_valid_block_hash
+    }
+
+    // Hash of the ancestor of a block at the specified height (which must not be higher than the block's)
+    fn get_ancestor_hash(&self, mut block_hash: u32, height: u32) -> u32 {
+        let mut block_wrapper = self.blocks.get(&block_hash).unwrap();
+
+        while block_wrapper.height > height {
+            block_hash = block_wrapper.header.previous_block_hash;
+            block_wrapper = self.blocks.get(&block_hash).unwrap();
+        }
+
+        block_hash
+    }
+
+    // Checks the transactions of a block, independently of the unspent transactions
+    fn check_block_body(&self, block: &Block) -> Result<(), NodeError> {
+        let block_hash = block.hash();
+        let prev_block_hash = block.header.previous_block_hash;
+
+        if self.is_deployment_active(prev_block_hash, DEPLOYMENT_MERKLE_ROOT)
+            && block.header.merkle_root != block.compute_merkle_root()
+        {
+            return Err(NodeError::InvalidMerkleRoot { block_hash });
+        }
+
+        if self.is_deployment_active(prev_block_hash, DEPLOYMENT_COINBASE_HEIGHT) {
+            let height = self.blocks.get(&prev_block_hash).unwrap().height + 1;
+            let coinbase_data = block
+                .transactions
+                .first()
+                .map(|transaction| transaction.coinbase_data.as_slice())
+                .unwrap_or_default();
+
+            if !coinbase_data.starts_with(&height.to_le_bytes()) {
+                return Err(NodeError::InvalidCoinbaseHeight { block_hash, height });
+            }
+        }
+
+        if block.transactions.len() > self.params.max_block_transactions {
+            return Err(NodeError::TooManyTransactions {
+                block_hash,
+                count: block.transactions.len(),
+            });
+        }
+
+        if block.get_size() > self.params.max_block_size {
+            return Err(NodeError::BlockTooLarge {
+                block_hash,
+                size: block.get_size(),
+            });
+        }
+
+        // The first transaction is the coinbase one, which creates money out of nothing
+        for (index, transaction) in block.transactions.iter().enumerate().skip(1) {
+            let error = if transaction.inputs.is_empty() {
+                TransactionError::EmptyInputs
+            } else if transaction.outputs.is_empty() {
+                TransactionError::EmptyOutputs
+            } else {
+                continue;
+            };
+
+            return Err(NodeError::InvalidTransaction {
+                block_hash,
+                transaction_index: index,
+                transaction_hash: transaction.hash(),
+                error,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Switches to the valid chain with the most work, disconnecting and connecting blocks as needed.
+    // Blocks that fail to connect are marked as invalid and the next best chain is tried.
+    fn activate_best_chain(&mut self) {
+        let mut disconnected_transactions = vec![];
+        let mut connected_block_hashes = vec![];
+
+        loop {
+            let best_block_hash = self.find_best_block_hash();
+
+            if best_block_hash == self.last_block_hash {
+                break;
+            }
+
+            let fork_block_hash = self.find_fork_block_hash(self.last_block_hash, best_block_hash);
+            // Finding the best header scans all the blocks, so it's only done once per chain switch
+            let best_header_hash = self.get_best_header_hash();
+            let mut block_hashes_to_connect = vec![];
+            let mut block_hash = best_block_hash;
+
+            while block_hash != fork_block_hash {
+                block_hashes_to_connect.push(block_hash);
+                block_hash = self.blocks.get(&block_hash).unwrap().header.previous_block_hash;
+            }
+
+            if self.blocks.get(&fork_block_hash).unwrap().height < self.get_finalized_height() {
+                // The branch may have been accepted before the current chain grew past its fork point.
+                // If the best chain is an ancestor of the current one, there is no branch to refuse: the current chain is kept.
+                let Some(&branch_block_hash) = block_hashes_to_connect.last() else {
+                    break;
+                };
+                let error = NodeError::ReorgTooDeep {
+                    block_hash: branch_block_hash,
+                    fork_height: self.blocks.get(&fork_block_hash).unwrap().height,
+                    finalized_height: self.get_finalized_height(),
+                };
+
+                self.refused_reorgs.push(best_block_hash);
+                self.mark_block_invalid(branch_block_hash, error);
+                continue;
+            }
+
+            while self.last_block_hash != fork_block_hash {
+                let transactions = self.disconnect_block(self.last_block_hash);
+
+                // Blocks are disconnected from the last one, but their transactions must be added back in chain order
+                disconnected_transactions.splice(0..0, transactions);
+            }
+
+            for block_hash in block_hashes_to_connect.into_iter().rev() {
+                let skip_signatures = self.is_assumed_valid(block_hash, best_header_hash);
+
+                if let Err(error) = self.connect_block(block_hash, skip_signatures) {
+                    self.mark_block_invalid(block_hash, error);
+                    break;
+                }
+
+                connected_block_hashes.push(block_hash);
+            }
+        }
+
+        if !disconnected_transactions.is_empty() {
+            self.update_mempool_for_reorg(disconnected_transactions);
+        }
+
+        // Orphan transactions may be waiting for transactions that have just been confirmed
+        for block_hash in connected_block_hashes {
+            let transactions = self.blocks.get(&block_hash).unwrap().transactions.clone().unwrap();
+
+            for transaction in &transactions {
+                self.process_orphan_transactions(transaction);
+            }
+        }
+    }
+
+    // Adds the transactions of the disconnected blocks back to the pool, and removes the transactions of the pool
+    // that are not valid anymore on the new chain (e.g. spending the coinbase of a disconnected block).
+    // The minimum fee rate is not checked: the transactions were already accepted (possibly as part of a package whose
+    // parents don't pay enough on their own), and it may have risen since.
+    fn update_mempool_for_reorg(&mut self, disconnected_transactions: Vec<Transaction>) {
+        let now = self.get_current_time();
+        let pool_entries = self.mempool.clear();
+
+        for transaction in disconnected_transactions {
+            // Transactions also included in the new chain are rejected as already confirmed
+            self.accept_transaction(transaction, now, true).ok();
+        }
+
+        for entry in pool_entries {
+            self.accept_transaction(entry.transaction, entry.time, true).ok();
+        }
+    }
+
+    // Last block of the valid chain with the most work, among the chains whose transactions have all been received
+    fn find_best_block_hash(&self) -> u32 {
+        *self
+            .blocks
+            .iter()
+            .filter(|(hash, block_wrapper)| {
+                block_wrapper.has_chain_transactions && !self.invalid_blocks.contains_key(hash)
+            })
+            .max_by_key(|(_, block_wrapper)| {
+                (block_wrapper.chain_work, Reverse(block_wrapper.sequence))
+            })
+            .unwrap()
+            .0
+    }
+
+    // Last common ancestor of two blocks
+    fn find_fork_block_hash(&self, mut hash_1: u32, mut hash_2: u32) -> u32 {
+        while hash_1 != hash_2 {
+            let block_wrapper_1 = self.blocks.get(&hash_1).unwrap();
+            let block_wrapper_2 = self.blocks.get(&hash_2).unwrap();
+
+            if block_wrapper_1.height >= block_wrapper_2.height {
+                hash_1 = block_wrapper_1.header.previous_block_hash;
+            }
+
+            if block_wrapper_2.height >= block_wrapper_1.height {
+                hash_2 = block_wrapper_2.header.previous_block_hash;
+            }
+        }
+
+        hash_1
+    }
+
+    // Marks a block as invalid, as well as all the blocks (including orphans) descending from it
+    fn mark_block_invalid(&mut self, block_hash: u32, error: NodeError) {
+        let mut descendant_hashes = vec![];
+        // Descendants refer to the block that is actually invalid
+        let ancestor_hash = match &error {
+            NodeError::InvalidAncestor { ancestor_hash, .. } => *ancestor_hash,
+            _ => block_hash,
+        };
+        let ancestor_kind = error.kind();
+
+        self.invalid_blocks.insert(block_hash, error);
+        descendant_hashes.push(block_hash);
+
+        while let Some(hash) = descendant_hashes.pop() {
+            let mut children_hashes: Vec<u32> = self
+                .orphan_blocks
+                .remove_children(hash)
+                .iter()
+                .map(|block| block.hash())
+                .collect();
+
+            if let Some(block_wrapper) = self.blocks.get(&hash) {
+                children_hashes.extend(&block_wrapper.next_blocks_hashes);
+            }
+
+            for child_hash in children_hashes {
+                self.invalid_blocks.insert(
+                    child_hash,
+                    NodeError::InvalidAncestor {
+                        block_hash: child_hash,
+                        ancestor_hash,
+                        ancestor_kind,
+                    },
+                );
+                descendant_hashes.push(child_hash);
+            }
+        }
+    }
+
+    // Validates the transactions of a block extending the current chain and applies them to the unspent transactions
+    fn connect_block(&mut self, block_hash: u32, skip_signatures: bool) -> Result<(), NodeError> {
+        let block_wrapper = self.blocks.get(&block_hash).unwrap();
+        let block = Block {
+            header: block_wrapper.header.clone(),
+            transactions: block_wrapper.transactions.clone().unwrap(),
+        };
+        let (report, signature_count) = self.check_block_transactions(&block, skip_signatures)?;
+
+        // At this point the block is valid
+
+        self.validation_stats.connected_blocks += 1;
+
+        if skip_signatures {
+            self.validation_stats.assumed_valid_blocks += 1;
+            self.validation_stats.skipped_signatures += signature_count;
+        } else {
+            self.validation_stats.checked_signatures += signature_count;
+        }
+
+        let median_time_past = self.get_median_time_past(block.header.previous_block_hash);
+        let mut spent_outputs = vec![];
+
+        // Transactions are applied in order, as they may spend the outputs of the previous ones
+        for transaction in &block.transactions {
+            let transaction_hash = transaction.hash();
+
+            // Remove spent transactions
+            for input in &transaction.inputs {
+                let prev_transaction = self
+                    .unspent_transactions
+                    .get_mut(&input.prev_transaction_hash)
+                    .unwrap();
+
+                let output = prev_transaction.unspent_outputs.remove(&input.output_index).unwrap();
+
+                spent_outputs.push(SpentOutput {
+                    transaction_hash: input.prev_transaction_hash,
+                    output_index: input.output_index,
+                    output,
+                    height: prev_transaction.height,
+                    median_time_past: prev_transaction.median_time_past,
+                });
+
+                if prev_transaction.unspent_outputs.is_empty() {
+                    self.unspent_transactions
+                        .remove(&input.prev_transaction_hash);
+                }
+            }
+
+            // Add new unspent transactions
+            let mut unspent_transaction = UnspentTransaction::new(transaction_hash, report.height, median_time_past);
+
+            for (output_index, output) in transaction.outputs.iter().enumerate() {
+                unspent_transaction
+                    .unspent_outputs
+                    .insert(output_index as u32, output.clone());
+            }
+
+            if !unspent_transaction.unspent_outputs.is_empty() {
+                self.unspent_transactions.insert(transaction_hash, unspent_transaction);
+            }
+
+            self.mempool.remove(transaction_hash);
+
+            // Other transactions of the pool spending the same outputs can't be mined anymore
+            for input in &transaction.inputs {
+                if let Some(conflicting_hash) = self
+                    .mempool
+                    .get_spending_transaction_hash(input.prev_transaction_hash, input.output_index)
+                {
+                    self.mempool.remove_with_descendants(conflicting_hash);
+                }
+            }
+        }
+
+        let transaction_hashes: Vec<u32> = block.transactions.iter().map(|transaction| transaction.hash()).collect();
+
+        self.fee_estimator
+            .process_block(report.height, &transaction_hashes, &self.mempool);
+        self.blocks.get_mut(&block_hash).unwrap().spent_outputs = spent_outputs;
+        self.last_block_hash = block_hash;
+
+        // TODO: adjust block difficulty
+
+        Ok(())
+    }
+
+    // Validates the transactions of a block extending the current chain against an overlay of the unspent transactions.
+    // Returns the validation report and the number of signatures involved.
+    fn check_block_transactions(&self, block: &Block, skip_signatures: bool) -> Result<(BlockValidationReport, u64), NodeError> {
+        let block_hash = block.hash();
+        let prev_block_hash = block.header.previous_block_hash;
+        let height = self.blocks.get(&prev_block_hash).unwrap().height + 1;
+        // Locktimes are compared to the median time past rather than the block timestamp, which can be manipulated by the miner
+        let median_time_past = self.get_median_time_past(prev_block_hash);
+
+        let mut overlay = UnspentOutputsOverlay::new(&self.unspent_transactions);
+        let mut signature_count = 0;
+        let mut anounced_reward = 0;
+        let mut fees = 0;
+        let mut created_outpoints = vec![];
+        let mut spent_outpoints = vec![];
+
+        if block.transactions.is_empty() {
+            // Coinbase transaction is missing
+            return Err(NodeError::InvalidCoinbaseTransaction { block_hash });
+        }
+
+        for (index, transaction) in block.transactions.iter().enumerate() {
+            let is_coinbase_transaction = index == 0;
+            let mut input_sum = 0;
+            let transaction_hash = transaction.hash();
+            let invalid_transaction = |error| NodeError::InvalidTransaction {
+                block_hash,
+                transaction_index: index,
+                transaction_hash,
+                error,
+            };
+
+            if is_coinbase_transaction {
+                input_sum += transaction.reward;
+                anounced_reward = transaction.reward;
+
+                if transaction.coinbase_data.len() > MAX_COINBASE_DATA_SIZE {
+                    return Err(invalid_transaction(TransactionError::InvalidCoinbaseData));
+                }
+            } else if transaction.reward != 0 {
+                return Err(invalid_transaction(TransactionError::InvalidReward));
+            } else if !transaction.coinbase_data.is_empty() {
+                return Err(invalid_transaction(TransactionError::InvalidCoinbaseData));
+            }
+
+            // Otherwise the outputs of the previous transaction would be lost (BIP30)
+            if overlay.has_unspent_outputs(transaction_hash) {
+                return Err(invalid_transaction(TransactionError::DuplicateTransaction));
+            }
+
+            input_sum += self
+                .spend_transaction_inputs(transaction, &mut overlay, height, median_time_past, skip_signatures)
+                .map_err(invalid_transaction)?;
+            signature_count += transaction.inputs.len() as u64;
+
+            for input in &transaction.inputs {
+                spent_outpoints.push((input.prev_transaction_hash, input.output_index));
+            }
+
+            for (output_index, output) in transaction.outputs.iter().enumerate() {
+                overlay.add(transaction_hash, output_index as u32, output.clone(), height, median_time_past);
+                created_outpoints.push((transaction_hash, output_index as u32));
+            }
+
+            let fee = Self::compute_fee(transaction, input_sum).map_err(invalid_transaction)?;
+
+            if is_coinbase_transaction && fee != 0 {
+                // The coinbase transaction should not have any fee
+                return Err(NodeError::InvalidCoinbaseTransaction { block_hash });
+            }
+
+            fees += fee;
+        }
+
+        let actual_reward = self.params.block_value + fees;
+
+        if actual_reward != anounced_reward {
+            // The coinbase transaction does not match the block's content
+            return Err(NodeError::InvalidCoinbaseReward {
+                block_hash,
+                announced_reward: anounced_reward,
+                actual_reward,
+            });
+        }
+
+        let report = BlockValidationReport {
+            block_hash,
+            height,
+            size: block.get_size(),
+            fees,
+            reward: actual_reward,
+            created_outpoints,
+            spent_outpoints,
+        };
+
+        Ok((report, signature_count))
+    }
+
+    // Checks that a transaction is final and that its inputs can be spent, then spends them in the overlay.
+    // Returns the sum of the spent outputs.
+    fn spend_transaction_inputs(
+        &self,
+        transaction: &Transaction,
+        overlay: &mut UnspentOutputsOverlay,
+        height: u32,
+        median_time_past: u64,
+        skip_signatures: bool,
+    ) -> Result<u64, TransactionError> {
+        if !transaction.is_final(height, median_time_past) {
+            return Err(TransactionError::NonFinal {
+                locktime: transaction.locktime,
+            });
+        }
+
+        let mut input_sum = 0;
+        let mut input_coins = vec![];
+
+        for (input_index, input) in transaction.inputs.iter().enumerate() {
+            // Outputs spent by a previous transaction (or a previous input) are not available anymore
+            let (prev_output, prev_height, prev_median_time_past) = overlay
+                .get(input.prev_transaction_hash, input.output_index)
+                .ok_or_else(|| match overlay.contains_transaction(input.prev_transaction_hash) {
+                    true => TransactionError::InvalidInputIndex { input_index },
+                    false => TransactionError::InvalidInputHash { input_index },
+                })?;
+
+            // In reality this check would be much more complex and involve cryptography
+            if !skip_signatures && input.signature != prev_output.recipient_public_key {
+                return Err(TransactionError::InvalidInputSignature { input_index });
+            }
+
+            input_sum += prev_output.value;
+            input_coins.push((prev_height, prev_median_time_past));
+            overlay.spend(input.prev_transaction_hash, input.output_index);
+        }
+
+        if !transaction.check_sequence_locks(&input_coins, height, median_time_past) {
+            return Err(TransactionError::SequenceLocksNotSatisfied);
+        }
+
+        Ok(input_sum)
+    }
+
+    // Difference between the inputs and the outputs of a transaction, which goes to the miner
+    fn compute_fee(transaction: &Transaction, input_sum: u64) -> Result<u64, TransactionError> {
+        let output_sum = transaction.outputs.iter().map(|output| output.value).sum();
+
+        match input_sum.checked_sub(output_sum) {
+            Some(fee) => Ok(fee),
+            None => Err(TransactionError::InvalidBalance {
+                input_sum,
+                output_sum,
+            }),
+        }
+    }
+
+    // Reverts the changes made by the last block of the current chain on the unspent transactions.
+    // Returns the transactions of the block, except the coinbase one, so they can be added back to the pool.
+    fn disconnect_block(&mut self, block_hash: u32) -> Vec<Transaction> {
+        let block_wrapper = self.blocks.get_mut(&block_hash).unwrap();
+
+        // Spent outputs are restored first, as some may belong to transactions of the block itself
+        for spent_output in block_wrapper.spent_outputs.drain(..) {
+            let transaction = self
+                .unspent_transactions
+                .entry(spent_output.transaction_hash)
+                .or_insert_with(|| {
+                    UnspentTransaction::new(
+                        spent_output.transaction_hash,
+                        spent_output.height,
+                        spent_output.median_time_past,
+                    )
+                });
+
+            transaction
+                .unspent_outputs
+                .insert(spent_output.output_index, spent_output.output);
+        }
+
+        for transaction in block_wrapper.transactions.as_ref().unwrap() {
+            self.unspent_transactions.remove(&transaction.hash());
+        }
+
+        self.last_block_hash = block_wrapper.header.previous_block_hash;
+
+        block_wrapper.transactions.as_ref().unwrap()[1..].to_vec()
+    }
+
+    pub fn get_chain_params(&self) -> &ChainParams {
+        &self.params
+    }
+
+    pub fn get_last_block_hash(&self) -> u32 {
+        self.last_block_hash
+    }
+
+    pub fn get_mempool(&self) -> &Mempool {
+        &self.mempool
+    }
+
+    // Maximum total size (in bytes) of the transactions waiting to be mined. Transactions with the lowest fee rate are evicted to fit it.
+    pub fn set_mempool_max_size(&mut self, max_size: usize) {
+        let now = self.get_current_time();
+
+        self.mempool.set_max_size(max_size, now);
+    }
+
+    // Duration after which a transaction is removed from the pool if it hasn't been mined
+    pub fn set_mempool_expiry(&mut self, expiry: u64) {
+        let now = self.get_current_time();
+
+        self.mempool.set_expiry(expiry);
+        self.mempool.expire(now);
+    }
+
+    // Saves the pool in the data directory, typically on shutdown
+    pub fn save_mempool(&self, data_dir: &Path) -> io::Result<()> {
+        self.mempool.save(&data_dir.join(MEMPOOL_FILE_NAME))
+    }
+
+    // Loads the pool saved in the data directory, typically on startup. The transactions are validated again against the
+    // current chain, and those that expired in the meantime are dropped. Returns the number of accepted and rejected transactions.
+    // The minimum fee rate is not checked, so the parents of a package paid for by their child are accepted again.
+    pub fn load_mempool(&mut self, data_dir: &Path) -> io::Result<(usize, usize)> {
+        let transactions = Mempool::read_file(&data_dir.join(MEMPOOL_FILE_NAME))?;
+        let now = self.get_current_time();
+        let mut accepted_count = 0;
+        let mut rejected_count = 0;
+
+        for (transaction, time) in transactions {
+            if now.saturating_sub(time) > self.mempool.get_expiry() {
+                rejected_count += 1;
+                continue;
+            }
+
+            match self.accept_transaction(transaction, time, true) {
+                Ok(_) => accepted_count += 1,
+                Err(_) => rejected_count += 1,
+            }
+        }
+
+        Ok((accepted_count, rejected_count))
+    }
+
+    // Transactions waiting to be mined, in the order they should be included in a block
+    pub fn get_awaiting_transactions(&self) -> Vec<Transaction> {
+        self.mempool
+            .iter_by_priority()
+            .map(|entry| entry.transaction.clone())
+            .collect()
+    }
+
+    // Validates a transaction against the unspent transactions and the transactions of the pool, then adds it to the pool
+    // Conflicting transactions of the pool are replaced if they allow it and if the new transaction pays more (BIP125).
+    // A transaction spending unknown outputs is kept aside until the transactions it spends arrive.
+    pub fn add_transaction(&mut self, transaction: Transaction) -> Result<MempoolAcceptance, MempoolError> {
+        self.process_transaction(transaction, None)
+    }
+
+    // Same as `add_transaction`, for a transaction relayed by a peer. The number of transactions a peer can make the node
+    // keep aside is limited, so it can't fill the pool of orphan transactions on its own.
+    pub fn add_peer_transaction(&mut self, transaction: Transaction, peer_id: u32) -> Result<MempoolAcceptance, MempoolError> {
+        self.process_transaction(transaction, Some(peer_id))
+    }
+
+    // Checks whether transactions would be accepted in the pool, without adding them. Each transaction may spend
+    // the outputs of the previous ones (or replace them), as if they had been added in order.
+    pub fn test_mempool_accept(&self, transactions: &[Transaction]) -> Vec<Result<MempoolAcceptance, MempoolError>> {
+        let now = self.get_current_time();
+        let mut mempool = self.mempool.clone();
+
+        mempool.expire(now);
+
+        transactions
+            .iter()
+            .map(|transaction| {
+                let (entry, replaced_hashes) = self.check_transaction(&mempool, transaction.clone(), now, false)?;
+
+                Self::insert_mempool_entry(&mut mempool, entry, replaced_hashes, now)
+            })
+            .collect()
+    }
+
+    // Adds a child transaction and its parents to the pool, parents first: every transaction but the last one must be
+    // spent by the last one. The package is accepted if its fee rate as a whole is high enough, even if some of its
+    // transactions don't pay enough on their own (e.g. a parent paid for by its child).
+    // Either all the transactions are added or none of them. Transactions already in the pool are skipped.
+    pub fn add_package(&mut self, transactions: Vec<Transaction>) -> Result<Vec<MempoolAcceptance>, MempoolError> {
+        let now = self.get_current_time();
+
+        self.mempool.expire(now);
+
+        let mut mempool = self.mempool.clone();
+        let acceptances = self.check_package(&mut mempool, &transactions, now)?;
+
+        self.mempool = mempool;
+
+        for transaction in &transactions {
+            self.process_orphan_transactions(transaction);
+        }
+
+        Ok(acceptances)
+    }
+
+    // Adds the transactions of a package to the specified pool, checking the package as a whole
+    fn check_package(&self, mempool: &mut Mempool, transactions: &[Transaction], now: u64) -> Result<Vec<MempoolAcceptance>, MempoolError> {
+        let child = match transactions.last() {
+            Some(transaction) => transaction,
+            None => return Ok(vec![]),
+        };
+        let transaction_hash = child.hash();
+        let package_size: usize = transactions.iter().map(|transaction| transaction.get_size()).sum();
+
+        if transactions.len() > MAX_PACKAGE_COUNT || package_size > MAX_PACKAGE_SIZE {
+            return Err(MempoolError::PackageTooLarge {
+                transaction_hash,
+                count: transactions.len(),
+                size: package_size,
+            });
+        }
+
+        for parent in &transactions[..transactions.len() - 1] {
+            let parent_hash = parent.hash();
+
+            if !child
+                .inputs
+                .iter()
+                .any(|input| input.prev_transaction_hash == parent_hash)
+            {
+                return Err(MempoolError::PackageNotChildWithParents {
+                    transaction_hash: parent_hash,
+                });
+            }
+        }
+
+        let mut acceptances = vec![];
+
+        for transaction in transactions {
+            if mempool.contains(transaction.hash()) {
+                continue;
+            }
+
+            let (entry, replaced_hashes) = self.check_transaction(mempool, transaction.clone(), now, true)?;
+
+            acceptances.push(Self::insert_mempool_entry(mempool, entry, replaced_hashes, now)?);
+        }
+
+        // A transaction of the package may have been evicted when adding the following ones
+        for acceptance in &acceptances {
+            if !mempool.contains(acceptance.transaction_hash) {
+                return Err(MempoolError::MempoolFull {
+                    transaction_hash: acceptance.transaction_hash,
+                });
+            }
+        }
+
+        if acceptances.is_empty() {
+            return Ok(acceptances);
+        }
+
+        let fee: u64 = acceptances.iter().map(|acceptance| acceptance.fee).sum();
+        let size: usize = acceptances.iter().map(|acceptance| acceptance.size).sum();
+        let fee_rate = fee * 1000 / size as u64;
+        let min_fee_rate = self.mempool.get_min_fee_rate(now);
+
+        if fee_rate < min_fee_rate {
+            return Err(MempoolError::PackageFeeTooLow {
+                transaction_hash,
+                fee_rate,
+                min_fee_rate,
+            });
+        }
+
+        Ok(acceptances)
+    }
+
+    // Fee rate a transaction should pay to be mined within `target_blocks` blocks, based on the recently mined transactions.
+    // Never below the minimum fee rate of the pool.
+    pub fn estimate_fee(&self, target_blocks: u32, confidence: FeeEstimateConfidence) -> Option<FeeEstimate> {
+        let mut estimate = self.fee_estimator.estimate_fee(target_blocks, confidence)?;
+
+        estimate.fee_rate = estimate
+            .fee_rate
+            .max(self.mempool.get_min_fee_rate(self.get_current_time()));
+
+        Some(estimate)
+    }
+
+    // Forgets the orphan transactions sent by a peer (e.g. when it disconnects), returning their number
+    pub fn remove_peer_orphan_transactions(&mut self, peer_id: u32) -> usize {
+        self.orphan_transactions.remove_from_peer(peer_id)
+    }
+
+    pub fn get_orphan_transactions(&self) -> &OrphanTransactionPool {
+        &self.orphan_transactions
+    }
+
+    fn process_transaction(&mut self, transaction: Transaction, peer_id: Option<u32>) -> Result<MempoolAcceptance, MempoolError> {
+        let now = self.get_current_time();
+
+        self.mempool.expire(now);
+
+        match self.accept_transaction(transaction.clone(), now, false) {
+            Ok(acceptance) => {
+                self.track_fee_estimate(&acceptance);
+                self.process_orphan_transactions(&transaction);
+                Ok(acceptance)
+            }
+            Err(
+                error @ MempoolError::InvalidTransaction {
+                    error: TransactionError::InvalidInputHash { .. },
+                    ..
+                },
+            ) => {
+                let transaction_hash = error.get_transaction_hash();
+                let missing_outpoints = self.get_missing_outpoints(&transaction);
+
+                // The spent transaction may also be known but about to be replaced by this one
+                if missing_outpoints.is_empty() || transaction.get_size() > MAX_ORPHAN_TRANSACTION_SIZE {
+                    return Err(error);
+                }
+
+                let prev_transaction_hash = missing_outpoints[0].0;
+
+                self.orphan_transactions
+                    .insert(transaction, peer_id, missing_outpoints);
+
+                Err(MempoolError::OrphanTransaction {
+                    transaction_hash,
+                    prev_transaction_hash,
+                })
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    // Adds to the pool the orphan transactions that were waiting for this transaction, and recursively their own orphans
+    fn process_orphan_transactions(&mut self, transaction: &Transaction) {
+        let now = self.get_current_time();
+        let mut parents = vec![(transaction.hash(), transaction.outputs.len())];
+
+        while let Some((parent_hash, output_count)) = parents.pop() {
+            for orphan in self.orphan_transactions.remove_children(parent_hash, output_count) {
+                let transaction_hash = orphan.transaction.hash();
+                let output_count = orphan.transaction.outputs.len();
+
+                match self.accept_transaction(orphan.transaction.clone(), now, false) {
+                    Ok(acceptance) => {
+                        self.track_fee_estimate(&acceptance);
+                        parents.push((transaction_hash, output_count));
+                    }
+                    // The transaction is still waiting for another parent
+                    Err(MempoolError::InvalidTransaction {
+                        error: TransactionError::InvalidInputHash { .. },
+                        ..
+                    }) => {
+                        let missing_outpoints = self.get_missing_outpoints(&orphan.transaction);
+
+                        self.orphan_transactions
+                            .insert(orphan.transaction, orphan.peer_id, missing_outpoints);
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+
+    // Watches a transaction accepted on its own for fee estimates. Transactions of packages, or coming back to the pool
+    // after a reorg or a restart, are not watched as their fee rate and entry height say little about the market.
+    fn track_fee_estimate(&mut self, acceptance: &MempoolAcceptance) {
+        let height = self.blocks.get(&self.last_block_hash).unwrap().height;
+
+        self.fee_estimator
+            .track(acceptance.transaction_hash, acceptance.fee_rate, height);
+    }
+
+    // Outputs spent by a transaction whose transaction is neither in the unspent transactions nor in the pool
+    fn get_missing_outpoints(&self, transaction: &Transaction) -> Vec<(u32, u32)> {
+        transaction
+            .inputs
+            .iter()
+            .filter(|input| {
+                !self.unspent_transactions.contains_key(&input.prev_transaction_hash)
+                    && !self.mempool.contains(input.prev_transaction_hash)
+            })
+            .map(|input| (input.prev_transaction_hash, input.output_index))
+            .collect()
+    }
+
+    // Adds a transaction to the pool, `time` being the time at which it entered the pool
+    fn accept_transaction(&mut self, transaction: Transaction, time: u64, skip_fee_rate: bool) -> Result<MempoolAcceptance, MempoolError> {
+        let now = self.get_current_time();
+        let (entry, replaced_hashes) = self.check_transaction(&self.mempool, transaction, time, skip_fee_rate)?;
+
+        Self::insert_mempool_entry(&mut self.mempool, entry, replaced_hashes, now)
+    }
+
+    // Validates a transaction against the unspent transactions and the specified pool.
+    // Returns the entry to add to the pool and the hashes of the pool transactions it replaces.
+    // The minimum fee rate is not checked for transactions of a package, which is checked as a whole instead, nor for
+    // transactions added back to the pool after a reorg or loaded after a restart.
+    fn check_transaction(&self, mempool: &Mempool, transaction: Transaction, time: u64, skip_fee_rate: bool) -> Result<(MempoolEntry, Vec<u32>), MempoolError> {
+        let transaction_hash = transaction.hash();
+        let invalid_transaction = |error| MempoolError::InvalidTransaction {
+            transaction_hash,
+            error,
+        };
+
+        if mempool.contains(transaction_hash) {
+            return Err(MempoolError::AlreadyInPool { transaction_hash });
+        }
+
+        if self.unspent_transactions.contains_key(&transaction_hash) {
+            return Err(MempoolError::AlreadyConfirmed { transaction_hash });
+        }
+
+        if transaction.inputs.is_empty() {
+            return Err(invalid_transaction(TransactionError::EmptyInputs));
+        }
+
+        if transaction.outputs.is_empty() {
+            return Err(invalid_transaction(TransactionError::EmptyOutputs));
+        }
+
+        if transaction.reward != 0 {
+            return Err(invalid_transaction(TransactionError::InvalidReward));
+        }
+
+        if !transaction.coinbase_data.is_empty() {
+            return Err(invalid_transaction(TransactionError::InvalidCoinbaseData));
+        }
+
+        let mut conflicting_hashes = vec![];
+
+        for (input_index, input) in transaction.inputs.iter().enumerate() {
+            if let Some(conflicting_transaction_hash) =
+                mempool.get_spending_transaction_hash(input.prev_transaction_hash, input.output_index)
+            {
+                if !mempool.is_replaceable(conflicting_transaction_hash) {
+                    return Err(MempoolError::Conflict {
+                        transaction_hash,
+                        input_index,
+                        conflicting_transaction_hash,
+                    });
+                }
+
+                if !conflicting_hashes.contains(&conflicting_transaction_hash) {
+                    conflicting_hashes.push(conflicting_transaction_hash);
+                }
+            }
+        }
+
+        let mut replaced_hashes = conflicting_hashes.clone();
+
+        for conflicting_hash in &conflicting_hashes {
+            for descendant_hash in mempool.get_descendant_hashes(*conflicting_hash) {
+                if !replaced_hashes.contains(&descendant_hash) {
+                    replaced_hashes.push(descendant_hash);
+                }
+            }
+        }
+
+        if replaced_hashes.len() > MAX_REPLACEMENT_EVICTIONS {
+            return Err(MempoolError::TooManyReplacements {
+                transaction_hash,
+                count: replaced_hashes.len(),
+            });
+        }
+
+        // The transaction is checked against the next block to be mined
+        let height = self.blocks.get(&self.last_block_hash).unwrap().height + 1;
+        let median_time_past = self.get_median_time_past(self.last_block_hash);
+        let mut overlay = UnspentOutputsOverlay::new(&self.unspent_transactions);
+
+        // Outputs of the transactions of the pool are considered to be included in the next block, except the replaced ones
+        for entry in mempool.iter().filter(|entry| !replaced_hashes.contains(&entry.hash)) {
+            for (output_index, output) in entry.transaction.outputs.iter().enumerate() {
+                overlay.add(entry.hash, output_index as u32, output.clone(), height, median_time_past);
+            }
+        }
+
+        let input_sum = self
+            .spend_transaction_inputs(&transaction, &mut overlay, height, median_time_past, false)
+            .map_err(invalid_transaction)?;
+
+        let fee = Self::compute_fee(&transaction, input_sum).map_err(invalid_transaction)?;
+        let entry = MempoolEntry::new(transaction, fee, time);
+        let min_fee_rate = mempool.get_min_fee_rate(self.get_current_time());
+
+        if !skip_fee_rate && entry.fee_rate < min_fee_rate {
+            return Err(MempoolError::FeeTooLow {
+                transaction_hash,
+                fee_rate: entry.fee_rate,
+                min_fee_rate,
+            });
+        }
+
+        Self::check_package_limits(mempool, &entry)?;
+
+        if !conflicting_hashes.is_empty() {
+            Self::check_replacement(mempool, &entry, &conflicting_hashes, &replaced_hashes)?;
+        }
+
+        Ok((entry, replaced_hashes))
+    }
+
+    // Removes the replaced transactions from the pool and adds the new one
+    fn insert_mempool_entry(mempool: &mut Mempool, entry: MempoolEntry, replaced_hashes: Vec<u32>, now: u64) -> Result<MempoolAcceptance, MempoolError> {
+        let acceptance = MempoolAcceptance {
+            transaction_hash: entry.hash,
+            fee: entry.fee,
+            size: entry.size,
+            fee_rate: entry.fee_rate,
+            replaced_transaction_hashes: replaced_hashes,
+        };
+
+        for replaced_hash in &acceptance.replaced_transaction_hashes {
+            mempool.remove(*replaced_hash);
+        }
+
+        if mempool.insert(entry, now).contains(&acceptance.transaction_hash) {
+            // The pool is full of transactions paying more
+            return Err(MempoolError::MempoolFull {
+                transaction_hash: acceptance.transaction_hash,
+            });
+        }
+
+        Ok(acceptance)
+    }
+
+    // Checks that a transaction doesn't make a chain of unconfirmed transactions too long or too large
+    fn check_package_limits(mempool: &Mempool, entry: &MempoolEntry) -> Result<(), MempoolError> {
+        let transaction_hash = entry.hash;
+        let ancestors: Vec<&MempoolEntry> = mempool
+            .get_transaction_ancestor_hashes(&entry.transaction)
+            .into_iter()
+            .map(|hash| mempool.get(hash).unwrap())
+            .collect();
+        let ancestor_count = ancestors.len() + 1;
+        let ancestor_size = entry.size + ancestors.iter().map(|ancestor| ancestor.size).sum::<usize>();
+
+        if ancestor_count > MAX_ANCESTOR_COUNT || ancestor_size > MAX_ANCESTOR_SIZE {
+            return Err(MempoolError::AncestorLimitExceeded {
+                transaction_hash,
+                count: ancestor_count,
+                size: ancestor_size,
+            });
+        }
+
+        for ancestor in ancestors {
+            if ancestor.descendant_count + 1 > MAX_DESCENDANT_COUNT
+                || ancestor.descendant_size + entry.size > MAX_DESCENDANT_SIZE
+            {
+                return Err(MempoolError::DescendantLimitExceeded {
+                    transaction_hash,
+                    ancestor_hash: ancestor.hash,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Checks that a transaction pays enough to replace the transactions of the pool it conflicts with (BIP125)
+    fn check_replacement(mempool: &Mempool, entry: &MempoolEntry, conflicting_hashes: &[u32], replaced_hashes: &[u32]) -> Result<(), MempoolError> {
+        let transaction_hash = entry.hash;
+        let conflicting_entries: Vec<&MempoolEntry> = conflicting_hashes
+            .iter()
+            .map(|hash| mempool.get(*hash).unwrap())
+            .collect();
+
+        // Otherwise the replacement could be less likely to be mined than the transactions it replaces
+        for (input_index, input) in entry.transaction.inputs.iter().enumerate() {
+            let is_unconfirmed = mempool.contains(input.prev_transaction_hash);
+            let was_spent = conflicting_entries.iter().any(|conflicting_entry| {
+                conflicting_entry
+                    .transaction
+                    .inputs
+                    .iter()
+                    .any(|conflicting_input| conflicting_input.prev_transaction_hash == input.prev_transaction_hash)
+            });
+
+            if is_unconfirmed && !was_spent {
+                return Err(MempoolError::NewUnconfirmedInput {
+                    transaction_hash,
+                    input_index,
+                });
+            }
+        }
+
+        for conflicting_entry in &conflicting_entries {
+            if entry.fee_rate <= conflicting_entry.fee_rate {
+                return Err(MempoolError::ReplacementFeeRateTooLow {
+                    transaction_hash,
+                    fee_rate: entry.fee_rate,
+                    replaced_fee_rate: conflicting_entry.fee_rate,
+                });
+            }
+        }
+
+        // The replacement pays for the replaced transactions, plus the relay of its own size
+        let replaced_fee: u64 = replaced_hashes
+            .iter()
+            .map(|hash| mempool.get(*hash).unwrap().fee)
+            .sum();
+        let min_fee = replaced_fee + INCREMENTAL_RELAY_FEE_RATE * entry.size as u64 / 1000;
+
+        if entry.fee < min_fee {
+            return Err(MempoolError::ReplacementFeeTooLow {
+                transaction_hash,
+                fee: entry.fee,
+                min_fee,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Median of the timestamps of the last `MEDIAN_TIME_SPAN` blocks, up to (and including) the specified one
+    fn get_median_time_past(&self, block_hash: u32) -> u64 {
+        let mut timestamps = vec![];
+        let mut block_wrapper = self.blocks.get(&block_hash).unwrap();
+
+        loop {
+            timestamps.push(block_wrapper.header.timestamp);
+
+            if block_wrapper.height == 0 || timestamps.len() == MEDIAN_TIME_SPAN {
+                break;
+            }
+
+            block_wrapper = self
+                .blocks
+                .get(&block_wrapper.header.previous_block_hash)
+                .unwrap();
+        }
+
+        timestamps.sort();
+        timestamps[timestamps.len() / 2]
+    }
+
+    fn check_hash_difficulty(&self, hash: u32) -> bool {
+        hash.leading_zeros() >= self.current_difficulty
+    }
+
+    fn get_current_time(&self) -> u64 {
+        self.clock.now()
     }
 
     pub fn print_unspent_transactions(&self, owners: &HashMap<u32, String>) {
@@ -1,12 +1,169 @@
-#[derive(Debug)]
+use super::node_error_kind::NodeErrorKind;
+use crate::transaction::transaction_error::TransactionError;
+use std::fmt;
+
+#[derive(Debug, Clone)]
 pub enum NodeError {
-    InvalidPrevBlockHash,
-    InvalidDifficulty,
-    InvalidTimestamp,
-    InvalidCoinbaseTransaction,
-    InvalidTransactionInputHash,
-    InvalidTransactionInputIndex,
-    InvalidTransactionInputSignature,
-    InvalidTransactionBalance,
-    InvalidTransactionReward,
-}
\ No newline at end of file
+    DuplicateBlock { block_hash: u32 },
+    OrphanBlock { block_hash: u32, prev_block_hash: u32 }, // The previous block is unknown, the block is kept until it arrives
+    OrphanHeader { block_hash: u32, prev_block_hash: u32 }, // The previous block is unknown
+    UnknownBlock { block_hash: u32 },
+    NotExtendingTip { block_hash: u32, prev_block_hash: u32 }, // Only blocks extending the current chain can be tested without being added
+    InvalidAncestor { block_hash: u32, ancestor_hash: u32, ancestor_kind: NodeErrorKind }, // Same kind as the error of the ancestor, e.g. a manually invalidated block doesn't make its descendants consensus invalid
+    ManuallyInvalidated { block_hash: u32 },
+    CannotInvalidateGenesisBlock { block_hash: u32 },
+    InvalidDifficulty { block_hash: u32 },
+    InvalidTimestamp { block_hash: u32, timestamp: u64 },
+    CheckpointMismatch { block_hash: u32, height: u32 },
+    ForkBeforeCheckpoint { block_hash: u32, height: u32 },
+    ReorgTooDeep { block_hash: u32, fork_height: u32, finalized_height: u32 }, // The block is on a branch forking below the finalized height
+    FutureTimestamp { block_hash: u32, timestamp: u64 },
+    InvalidCoinbaseTransaction { block_hash: u32 },
+    InvalidCoinbaseReward { block_hash: u32, announced_reward: u64, actual_reward: u64 },
+    InvalidCoinbaseHeight { block_hash: u32, height: u32 },
+    InvalidMerkleRoot { block_hash: u32 },
+    BlockTooLarge { block_hash: u32, size: usize },
+    TooManyTransactions { block_hash: u32, count: usize },
+    InvalidTransaction {
+        block_hash: u32,
+        transaction_index: usize,
+        transaction_hash: u32,
+        error: TransactionError,
+    },
+}
+
+impl NodeError {
+    pub fn get_block_hash(&self) -> u32 {
+        match self {
+            Self::DuplicateBlock { block_hash }
+            | Self::OrphanBlock { block_hash, .. }
+            | Self::OrphanHeader { block_hash, .. }
+            | Self::UnknownBlock { block_hash }
+            | Self::NotExtendingTip { block_hash, .. }
+            | Self::InvalidAncestor { block_hash, .. }
+            | Self::ManuallyInvalidated { block_hash }
+            | Self::CannotInvalidateGenesisBlock { block_hash }
+            | Self::InvalidDifficulty { block_hash }
+            | Self::InvalidTimestamp { block_hash, .. }
+            | Self::CheckpointMismatch { block_hash, .. }
+            | Self::ForkBeforeCheckpoint { block_hash, .. }
+            | Self::ReorgTooDeep { block_hash, .. }
+            | Self::FutureTimestamp { block_hash, .. }
+            | Self::InvalidCoinbaseTransaction { block_hash }
+            | Self::InvalidCoinbaseReward { block_hash, .. }
+            | Self::InvalidCoinbaseHeight { block_hash, .. }
+            | Self::InvalidMerkleRoot { block_hash }
+            | Self::BlockTooLarge { block_hash, .. }
+            | Self::TooManyTransactions { block_hash, .. }
+            | Self::InvalidTransaction { block_hash, .. } => *block_hash,
+        }
+    }
+
+    pub fn kind(&self) -> NodeErrorKind {
+        match self {
+            Self::InvalidAncestor { ancestor_kind, .. } => *ancestor_kind,
+            Self::OrphanBlock { .. } | Self::OrphanHeader { .. } | Self::FutureTimestamp { .. } => {
+                NodeErrorKind::TemporarilyUnavailable
+            }
+            Self::DuplicateBlock { .. }
+            | Self::UnknownBlock { .. }
+            | Self::NotExtendingTip { .. }
+            | Self::ManuallyInvalidated { .. }
+            | Self::CannotInvalidateGenesisBlock { .. }
+            | Self::ReorgTooDeep { .. } => NodeErrorKind::Rejected,
+            _ => NodeErrorKind::ConsensusInvalid,
+        }
+    }
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "block {:#010x}: ", self.get_block_hash())?;
+
+        match self {
+            Self::DuplicateBlock { .. } => write!(f, "already known"),
+            Self::OrphanBlock { prev_block_hash, .. } => write!(
+                f,
+                "previous block {:#010x} is unknown, waiting for it",
+                prev_block_hash
+            ),
+            Self::OrphanHeader { prev_block_hash, .. } => {
+                write!(f, "previous block {:#010x} is unknown", prev_block_hash)
+            }
+            Self::UnknownBlock { .. } => write!(f, "unknown"),
+            Self::NotExtendingTip { prev_block_hash, .. } => write!(
+                f,
+                "previous block {:#010x} is not the last block of the current chain",
+                prev_block_hash
+            ),
+            Self::InvalidAncestor { ancestor_hash, .. } => {
+                write!(f, "descends from invalid block {:#010x}", ancestor_hash)
+            }
+            Self::ManuallyInvalidated { .. } => write!(f, "manually invalidated"),
+            Self::CannotInvalidateGenesisBlock { .. } => {
+                write!(f, "the genesis block can't be invalidated")
+            }
+            Self::InvalidDifficulty { .. } => write!(f, "invalid difficulty"),
+            Self::InvalidTimestamp { timestamp, .. } => {
+                write!(f, "invalid timestamp {}", timestamp)
+            }
+            Self::CheckpointMismatch { height, .. } => {
+                write!(f, "doesn't match the checkpoint at height {}", height)
+            }
+            Self::ForkBeforeCheckpoint { height, .. } => {
+                write!(f, "forks the chain at height {}, before a checkpoint", height)
+            }
+            Self::ReorgTooDeep {
+                fork_height,
+                finalized_height,
+                ..
+            } => write!(
+                f,
+                "forks the chain at height {}, below the finalized height {}",
+                fork_height, finalized_height
+            ),
+            Self::FutureTimestamp { timestamp, .. } => {
+                write!(f, "timestamp {} is too far in the future", timestamp)
+            }
+            Self::InvalidCoinbaseTransaction { .. } => {
+                write!(f, "missing or invalid coinbase transaction")
+            }
+            Self::InvalidCoinbaseReward {
+                announced_reward,
+                actual_reward,
+                ..
+            } => write!(
+                f,
+                "coinbase reward is {} units instead of {}",
+                announced_reward, actual_reward
+            ),
+            Self::InvalidCoinbaseHeight { height, .. } => {
+                write!(f, "coinbase data doesn't start with the height {}", height)
+            }
+            Self::InvalidMerkleRoot { .. } => write!(f, "invalid merkle root"),
+            Self::BlockTooLarge { size, .. } => write!(f, "too large ({} bytes)", size),
+            Self::TooManyTransactions { count, .. } => {
+                write!(f, "too many transactions ({})", count)
+            }
+            Self::InvalidTransaction {
+                transaction_index,
+                transaction_hash,
+                error,
+                ..
+            } => write!(
+                f,
+                "transaction {} ({:#010x}) is invalid: {}",
+                transaction_index, transaction_hash, error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidTransaction { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
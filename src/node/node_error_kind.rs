@@ -0,0 +1,6 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeErrorKind {
+    ConsensusInvalid,       // The block breaks the consensus rules, the peer that sent it should be banned
+    TemporarilyUnavailable, // The block can't be validated yet (e.g. its parent is missing) but may be valid later
+    Rejected,               // The block or request is refused because of the node state or policy
+}
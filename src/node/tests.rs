@@ -0,0 +1,1391 @@
+use super::{chain_params::ChainParams, node::Node, node_error::NodeError, node_error_kind::NodeErrorKind};
+use crate::{
+    block::{block::Block, block_header::BlockHeader, block_template_error::BlockTemplateError},
+    constants::{
+        MAX_ORPHAN_BLOCKS, MAX_ORPHAN_TRANSACTIONS, MAX_ORPHAN_TRANSACTIONS_PER_PEER, MEMPOOL_FILE_NAME, MIN_RELAY_FEE_RATE,
+        ROLLING_FEE_RATE_HALF_LIFE, SEQUENCE_FINAL, TIMESTAMP_64_BIT_VERSION,
+    },
+    deployment::deployment_state::DeploymentState,
+    mempool::mempool_error::MempoolError,
+    transaction::{
+        transaction::Transaction, transaction_error::TransactionError, transaction_input::TransactionInput,
+        transaction_output::TransactionOutput,
+    },
+    utils::{
+        clock::{Clock, MockClock},
+        serializer::Serializable,
+    },
+};
+
+// Block extending the current chain
+fn build_block(node: &Node, version: u32, timestamp: u64) -> Block {
+    build_block_on(node, node.get_last_block_hash(), version, timestamp)
+}
+
+// Block paying the block value to a single key, with a nonce meeting the difficulty
+fn build_block_on(node: &Node, previous_block_hash: u32, version: u32, timestamp: u64) -> Block {
+    let params = node.get_chain_params();
+    let mut block = Block {
+        header: BlockHeader {
+            version,
+            previous_block_hash,
+            merkle_root: 0,
+            timestamp,
+            difficulty_target: params.starting_difficulty,
+            nonce: 0,
+        },
+        transactions: vec![Transaction {
+            version,
+            reward: params.block_value,
+            inputs: vec![],
+            outputs: vec![TransactionOutput {
+                recipient_public_key: 1,
+                value: params.block_value,
+            }],
+            locktime: 0,
+            // Otherwise the coinbase transactions of all the blocks would have the same hash
+            coinbase_data: timestamp.to_le_bytes().to_vec(),
+        }],
+    };
+
+    seal_block(&mut block);
+    block
+}
+
+// Spends the first output of a transaction, the signature being the public key of its recipient
+fn spend(prev_transaction_hash: u32, owner_public_key: u32, recipient_public_key: u32, value: u64) -> Transaction {
+    Transaction {
+        version: 1,
+        reward: 0,
+        inputs: vec![TransactionInput {
+            prev_transaction_hash,
+            output_index: 0,
+            signature: owner_public_key,
+            sequence: 0,
+        }],
+        outputs: vec![TransactionOutput {
+            recipient_public_key,
+            value,
+        }],
+        locktime: 0,
+        coinbase_data: vec![],
+    }
+}
+
+// Updates the merkle root after the transactions of a block changed, and finds a new nonce
+fn seal_block(block: &mut Block) {
+    block.header.merkle_root = block.compute_merkle_root();
+    block.header.nonce = 0;
+
+    while !block.header.has_valid_proof_of_work() {
+        block.header.nonce += 1;
+    }
+}
+
+#[test]
+fn timestamps_past_2106_need_a_64_bit_version() {
+    let now = u32::MAX as u64 + 100;
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(MockClock::new(now)));
+
+    assert!(matches!(
+        node.add_block(build_block(&node, 1, now)),
+        Err(NodeError::InvalidTimestamp { .. })
+    ));
+    assert!(node.add_block(build_block(&node, TIMESTAMP_64_BIT_VERSION, now)).is_ok());
+}
+
+#[test]
+fn future_timestamps_are_checked_past_2106() {
+    let now = u32::MAX as u64 - 10;
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(MockClock::new(now)));
+    let max_timestamp = now + node.get_chain_params().max_ahead_of_time_timestamp_secs;
+
+    assert!(max_timestamp > u32::MAX as u64);
+    assert!(matches!(
+        node.add_block(build_block(&node, TIMESTAMP_64_BIT_VERSION, max_timestamp + 1)),
+        Err(NodeError::FutureTimestamp { .. })
+    ));
+
+    // Not too far in the future, but the timestamp doesn't fit in 32 bits
+    assert!(matches!(
+        node.add_block(build_block(&node, 1, max_timestamp)),
+        Err(NodeError::InvalidTimestamp { .. })
+    ));
+    assert!(node.add_block(build_block(&node, TIMESTAMP_64_BIT_VERSION, max_timestamp)).is_ok());
+}
+
+#[test]
+fn non_final_transactions_are_rejected() {
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(MockClock::new(1_000)));
+    // The next block has height 1
+    let transaction = Transaction {
+        version: 1,
+        reward: 0,
+        inputs: vec![TransactionInput {
+            prev_transaction_hash: 1,
+            output_index: 0,
+            signature: 1,
+            sequence: 0,
+        }],
+        outputs: vec![TransactionOutput {
+            recipient_public_key: 2,
+            value: 1,
+        }],
+        locktime: 1,
+        coinbase_data: vec![],
+    };
+
+    assert!(matches!(
+        node.add_transaction(transaction.clone()),
+        Err(MempoolError::InvalidTransaction {
+            error: TransactionError::NonFinal { locktime: 1 },
+            ..
+        })
+    ));
+
+    let mut block = build_block(&node, 1, 1_000);
+
+    block.transactions.push(transaction);
+    seal_block(&mut block);
+
+    assert!(matches!(
+        node.add_block(block),
+        Err(NodeError::InvalidTransaction {
+            transaction_index: 1,
+            error: TransactionError::NonFinal { .. },
+            ..
+        })
+    ));
+}
+
+#[test]
+fn chains_run_side_by_side() {
+    let clock = MockClock::new(1_000);
+    let mut main_node = Node::with_clock(ChainParams::main(), Box::new(clock.clone()));
+    let mut regtest_node = Node::with_clock(ChainParams::regtest(), Box::new(clock));
+    let main_block = build_block(&main_node, 1, 1_000);
+    let regtest_block = build_block(&regtest_node, 1, 1_000);
+
+    assert_ne!(main_node.get_last_block_hash(), regtest_node.get_last_block_hash());
+    assert!(main_node.add_block(build_block(&regtest_node, 1, 1_000)).is_err());
+    assert!(main_node.add_block(main_block).is_ok());
+    assert!(regtest_node.add_block(regtest_block).is_ok());
+}
+
+#[test]
+fn genesis_block_hash_covers_its_transactions() {
+    let allocations = vec![TransactionOutput {
+        recipient_public_key: 1,
+        value: 1000,
+    }];
+    let genesis_block = Block::genesis(1, 0, 0, "Private network", allocations.clone());
+
+    // The hash depends on the message and the allocations, not only on the header fields
+    assert_ne!(genesis_block.hash(), Block::genesis(1, 0, 0, "Other network", allocations).hash());
+    assert_ne!(genesis_block.hash(), Block::genesis(1, 0, 0, "Private network", vec![]).hash());
+    assert_eq!(genesis_block.header.merkle_root, genesis_block.compute_merkle_root());
+
+    let mut node = Node::with_clock(
+        ChainParams {
+            genesis_block: genesis_block.clone(),
+            ..ChainParams::regtest()
+        },
+        Box::new(MockClock::new(1_000)),
+    );
+
+    assert_eq!(node.get_last_block_hash(), genesis_block.hash());
+
+    // The allocations can be spent right away
+    let mut block = build_block(&node, 1, 1_000);
+
+    block.transactions.push(Transaction {
+        version: 1,
+        reward: 0,
+        inputs: vec![TransactionInput {
+            prev_transaction_hash: genesis_block.transactions[0].hash(),
+            output_index: 0,
+            signature: 1,
+            sequence: 0,
+        }],
+        outputs: vec![TransactionOutput {
+            recipient_public_key: 2,
+            value: 1000,
+        }],
+        locktime: 0,
+        coinbase_data: vec![],
+    });
+    seal_block(&mut block);
+
+    assert!(node.add_block(block).is_ok());
+}
+
+#[test]
+fn orphan_blocks_are_connected_when_their_parent_arrives() {
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(MockClock::new(1_000)));
+    let first_block = build_block(&node, 1, 1_000);
+    let second_block = build_block_on(&node, first_block.hash(), 1, 1_001);
+    let third_block = build_block_on(&node, second_block.hash(), 1, 1_002);
+    let first_block_hash = first_block.hash();
+    let third_block_hash = third_block.hash();
+
+    assert!(matches!(node.add_block(third_block), Err(NodeError::OrphanBlock { .. })));
+    assert!(matches!(node.add_block(second_block), Err(NodeError::OrphanBlock { .. })));
+    // The second block is waiting for the first one, which is the only one to request
+    assert_eq!(node.get_missing_block_hashes(), vec![first_block_hash]);
+
+    node.add_block(first_block).unwrap();
+
+    assert_eq!(node.get_last_block_hash(), third_block_hash);
+    assert!(node.get_missing_block_hashes().is_empty());
+}
+
+#[test]
+fn oldest_orphan_blocks_are_evicted() {
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(MockClock::new(1_000)));
+
+    // Each block waits for a different unknown parent
+    for unknown_parent_hash in 0..MAX_ORPHAN_BLOCKS as u32 + 1 {
+        let block = build_block_on(&node, unknown_parent_hash + 1, 1, 1_000);
+
+        assert!(matches!(node.add_block(block), Err(NodeError::OrphanBlock { .. })));
+    }
+
+    let missing_block_hashes = node.get_missing_block_hashes();
+
+    assert_eq!(missing_block_hashes.len(), MAX_ORPHAN_BLOCKS);
+    assert!(!missing_block_hashes.contains(&1));
+}
+
+#[test]
+fn invalidated_blocks_can_be_reconsidered() {
+    let mut node = Node::new(ChainParams::regtest());
+    let genesis_hash = node.get_last_block_hash();
+    let block_1 = build_block(&node, 1, 1);
+    let block_1_hash = block_1.hash();
+    node.add_block(block_1).unwrap();
+    let block_2 = build_block(&node, 1, 2);
+    let block_2_hash = block_2.hash();
+    node.add_block(block_2).unwrap();
+
+    // A competing chain, shorter than the current one
+    let fork_block = build_block_on(&node, genesis_hash, 1, 3);
+    let fork_block_hash = fork_block.hash();
+    node.add_block(fork_block).unwrap();
+    assert_eq!(node.get_last_block_hash(), block_2_hash);
+
+    node.invalidate_block(block_1_hash).unwrap();
+    assert_eq!(node.get_last_block_hash(), fork_block_hash);
+    assert!(matches!(
+        node.get_invalid_blocks().get(&block_1_hash),
+        Some(NodeError::ManuallyInvalidated { .. })
+    ));
+    assert!(matches!(
+        node.get_invalid_blocks().get(&block_2_hash),
+        Some(NodeError::InvalidAncestor { .. })
+    ));
+
+    node.reconsider_block(block_2_hash).unwrap();
+    assert_eq!(node.get_last_block_hash(), block_2_hash);
+    assert!(node.get_invalid_blocks().is_empty());
+}
+
+#[test]
+fn descendants_of_invalid_blocks_are_rejected() {
+    let mut node = Node::new(ChainParams::regtest());
+    let block_1 = build_block(&node, 1, 1);
+    let block_1_hash = block_1.hash();
+    node.add_block(block_1).unwrap();
+    let block_2 = build_block(&node, 1, 2);
+    node.invalidate_block(block_1_hash).unwrap();
+
+    assert!(matches!(node.add_block(block_2), Err(NodeError::InvalidAncestor { .. })));
+    assert!(matches!(
+        node.invalidate_block(node.get_last_block_hash()),
+        Err(NodeError::CannotInvalidateGenesisBlock { .. })
+    ));
+}
+
+#[test]
+fn headers_are_accepted_before_their_blocks() {
+    let mut node = Node::new(ChainParams::regtest());
+    let mut blocks = vec![];
+
+    // Blocks are built by a second node, only their headers are sent first
+    let mut miner_node = Node::new(ChainParams::regtest());
+
+    for timestamp in 1..=3 {
+        let block = build_block(&miner_node, 1, timestamp);
+        blocks.push(block.clone());
+        miner_node.add_block(block).unwrap();
+    }
+
+    let genesis_hash = node.get_last_block_hash();
+    let block_hashes: Vec<u32> = blocks.iter().map(|block| block.hash()).collect();
+
+    for block in &blocks {
+        node.accept_header(&block.header).unwrap();
+    }
+
+    assert_eq!(node.get_best_header_hash(), block_hashes[2]);
+    assert_eq!(node.get_blocks_to_download(), block_hashes);
+    assert_eq!(node.get_last_block_hash(), genesis_hash);
+
+    // The bodies arrive in reverse order, the chain can only move forward once the first one is received
+    for block in blocks.into_iter().rev() {
+        node.add_block(block).unwrap();
+    }
+
+    assert_eq!(node.get_last_block_hash(), block_hashes[2]);
+    assert!(node.get_blocks_to_download().is_empty());
+}
+
+#[test]
+fn blocks_over_the_consensus_limits_are_rejected() {
+    let params = ChainParams {
+        max_block_transactions: 1,
+        ..ChainParams::regtest()
+    };
+    let mut node = Node::new(params);
+    let mut block = build_block(&node, 1, 1);
+
+    block.transactions.push(block.transactions[0].clone());
+    seal_block(&mut block);
+    assert!(matches!(node.add_block(block), Err(NodeError::TooManyTransactions { .. })));
+
+    let block = build_block(&node, 1, 1);
+    let params = ChainParams {
+        max_block_size: block.get_size() - 1,
+        ..ChainParams::regtest()
+    };
+    let mut node = Node::new(params);
+
+    assert!(matches!(node.add_block(block), Err(NodeError::BlockTooLarge { .. })));
+}
+
+#[test]
+fn transactions_without_inputs_or_outputs_are_rejected() {
+    let mut node = Node::new(ChainParams::regtest());
+    let output = TransactionOutput {
+        recipient_public_key: 2,
+        value: 1,
+    };
+    let input = TransactionInput {
+        prev_transaction_hash: 1,
+        output_index: 0,
+        signature: 1,
+        sequence: 0,
+    };
+
+    let mut block = build_block(&node, 1, 1);
+    block.transactions.push(Transaction {
+        version: 1,
+        reward: 0,
+        inputs: vec![],
+        outputs: vec![output],
+        locktime: 0,
+        coinbase_data: vec![],
+    });
+    seal_block(&mut block);
+    assert!(matches!(
+        node.add_block(block),
+        Err(NodeError::InvalidTransaction {
+            error: TransactionError::EmptyInputs,
+            ..
+        })
+    ));
+
+    let mut block = build_block(&node, 1, 2);
+    block.transactions.push(Transaction {
+        version: 1,
+        reward: 0,
+        inputs: vec![input],
+        outputs: vec![],
+        locktime: 0,
+        coinbase_data: vec![],
+    });
+    seal_block(&mut block);
+    assert!(matches!(
+        node.add_block(block),
+        Err(NodeError::InvalidTransaction {
+            error: TransactionError::EmptyOutputs,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn deployment_status_is_available_from_genesis() {
+    let mut node = Node::new(ChainParams::regtest());
+    let window = node.get_chain_params().deployment_window;
+
+    assert!(node
+        .deployment_status()
+        .iter()
+        .all(|status| status.state == DeploymentState::Defined && status.window_elapsed == 1 && status.window_signals == 0));
+
+    // The first window includes the genesis block
+    for timestamp in 1..window as u64 {
+        node.add_block(build_block(&node, 1, timestamp)).unwrap();
+    }
+
+    assert!(node
+        .deployment_status()
+        .iter()
+        .all(|status| status.state == DeploymentState::Started));
+}
+
+#[test]
+fn blocks_must_follow_the_checkpoints() {
+    let mut miner_node = Node::new(ChainParams::regtest());
+    let genesis_hash = miner_node.get_last_block_hash();
+    let block_1 = build_block(&miner_node, 1, 1);
+    miner_node.add_block(block_1.clone()).unwrap();
+    let block_2 = build_block(&miner_node, 1, 2);
+
+    let params = ChainParams {
+        checkpoints: vec![(2, block_2.hash())],
+        ..ChainParams::regtest()
+    };
+    let mut node = Node::new(params);
+    let block_1_hash = node.add_block(block_1).unwrap();
+
+    assert!(matches!(
+        node.add_block(build_block_on(&node, block_1_hash, 1, 3)),
+        Err(NodeError::CheckpointMismatch { .. })
+    ));
+    node.add_block(block_2).unwrap();
+
+    // The chain can't be rewritten below the checkpoint anymore
+    assert!(matches!(
+        node.add_block(build_block_on(&node, genesis_hash, 1, 4)),
+        Err(NodeError::ForkBeforeCheckpoint { .. })
+    ));
+}
+
+#[test]
+fn signatures_are_skipped_up_to_the_assume_valid_block() {
+    let mut miner_node = Node::new(ChainParams::regtest());
+    let block_1 = build_block(&miner_node, 1, 1);
+    let coinbase_hash = block_1.transactions[0].hash();
+    miner_node.add_block(block_1.clone()).unwrap();
+
+    let mut block_2 = build_block(&miner_node, 1, 2);
+    block_2.transactions.push(Transaction {
+        version: 1,
+        reward: 0,
+        inputs: vec![TransactionInput {
+            prev_transaction_hash: coinbase_hash,
+            output_index: 0,
+            signature: 1,
+            sequence: 0,
+        }],
+        outputs: vec![TransactionOutput {
+            recipient_public_key: 2,
+            value: miner_node.get_chain_params().block_value,
+        }],
+        locktime: 0,
+        coinbase_data: vec![],
+    });
+    seal_block(&mut block_2);
+
+    let params = ChainParams {
+        assume_valid_block_hash: Some(block_2.hash()),
+        ..ChainParams::regtest()
+    };
+    let mut node = Node::new(params);
+    let mut checking_node = Node::new(ChainParams::regtest());
+
+    // The assume-valid block must be known to be part of the best chain of headers
+    node.accept_header(&block_1.header).unwrap();
+    node.accept_header(&block_2.header).unwrap();
+
+    for block in [block_1, block_2] {
+        node.add_block(block.clone()).unwrap();
+        checking_node.add_block(block).unwrap();
+    }
+
+    let stats = node.get_validation_stats();
+    assert_eq!(stats.connected_blocks, 2);
+    assert_eq!(stats.assumed_valid_blocks, 2);
+    assert_eq!(stats.skipped_signatures, 1);
+    assert_eq!(stats.checked_signatures, 0);
+
+    let stats = checking_node.get_validation_stats();
+    assert_eq!(stats.connected_blocks, 2);
+    assert_eq!(stats.assumed_valid_blocks, 0);
+    assert_eq!(stats.checked_signatures, 1);
+}
+
+#[test]
+fn finalized_blocks_cannot_be_invalidated() {
+    let mut node = Node::new(ChainParams::regtest());
+    let mut block_hashes = vec![];
+
+    for timestamp in 1..=10 {
+        block_hashes.push(node.add_block(build_block(&node, 1, timestamp)).unwrap());
+    }
+
+    node.set_max_reorg_depth(Some(2));
+
+    assert!(matches!(node.invalidate_block(block_hashes[4]), Err(NodeError::ReorgTooDeep { .. })));
+    assert_eq!(node.get_last_block_hash(), block_hashes[9]);
+
+    node.invalidate_block(block_hashes[8]).unwrap();
+
+    assert_eq!(node.get_last_block_hash(), block_hashes[7]);
+}
+
+#[test]
+fn branches_forking_below_the_finalized_height_are_refused() {
+    let mut node = Node::new(ChainParams::regtest());
+    let mut branch_node = Node::new(ChainParams::regtest());
+    let mut branch_blocks = vec![];
+
+    node.set_max_reorg_depth(Some(1));
+
+    // The headers of the branch are received while it is still allowed to replace the current chain
+    for timestamp in 1..=4 {
+        let block = build_block(&branch_node, 1, timestamp);
+
+        node.accept_header(&block.header).unwrap();
+        branch_blocks.push(block.clone());
+        branch_node.add_block(block).unwrap();
+    }
+
+    for timestamp in 11..=13 {
+        node.add_block(build_block(&node, 1, timestamp)).unwrap();
+    }
+
+    let last_block_hash = node.get_last_block_hash();
+    let branch_last_block_hash = branch_blocks[3].hash();
+
+    assert_eq!(node.get_finalized_height(), 2);
+
+    // A new branch is refused as soon as its header is received
+    assert!(matches!(
+        node.add_block(build_block_on(&node, branch_blocks[0].header.previous_block_hash, 1, 20)),
+        Err(NodeError::ReorgTooDeep { .. })
+    ));
+    assert!(node.get_refused_reorgs().is_empty());
+
+    // The known branch only gets more work than the current chain once all its blocks are received
+    for block in branch_blocks {
+        let _ = node.add_block(block);
+    }
+
+    assert_eq!(node.get_last_block_hash(), last_block_hash);
+    assert_eq!(node.get_refused_reorgs(), &[branch_last_block_hash]);
+    assert!(matches!(
+        node.get_invalid_blocks().get(&branch_last_block_hash),
+        Some(NodeError::InvalidAncestor { .. })
+    ));
+}
+
+#[test]
+fn descendants_of_invalid_blocks_have_the_kind_of_the_root_error() {
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(MockClock::new(1_000)));
+    let genesis_hash = node.get_last_block_hash();
+
+    let orphan_error = node.add_block(build_block_on(&node, 123, 1, 1)).unwrap_err();
+    assert_eq!(orphan_error.kind(), NodeErrorKind::TemporarilyUnavailable);
+
+    // A block whose transaction is not final yet breaks the consensus rules, and so do its descendants
+    let mut invalid_block = build_block(&node, 1, 2);
+    let mut transaction = invalid_block.transactions[0].clone();
+    transaction.reward = 0;
+    transaction.inputs = vec![TransactionInput {
+        prev_transaction_hash: 1,
+        output_index: 0,
+        signature: 1,
+        sequence: 0,
+    }];
+    transaction.locktime = 100;
+    invalid_block.transactions.push(transaction);
+    seal_block(&mut invalid_block);
+    let invalid_block_hash = invalid_block.hash();
+
+    let error = node.add_block(invalid_block).unwrap_err();
+    assert_eq!(error.kind(), NodeErrorKind::ConsensusInvalid);
+
+    let error = node.add_block(build_block_on(&node, invalid_block_hash, 1, 3)).unwrap_err();
+    assert!(matches!(error, NodeError::InvalidAncestor { ancestor_hash, .. } if ancestor_hash == invalid_block_hash));
+    assert_eq!(error.kind(), NodeErrorKind::ConsensusInvalid);
+
+    // A manually invalidated block is only refused by this node, so are its descendants
+    let block_hash = node.add_block(build_block_on(&node, genesis_hash, 1, 4)).unwrap();
+    let child_block = build_block(&node, 1, 5);
+    let child_block_hash = child_block.hash();
+    node.add_block(child_block).unwrap();
+    node.invalidate_block(block_hash).unwrap();
+
+    assert_eq!(
+        node.get_invalid_blocks().get(&child_block_hash).unwrap().kind(),
+        NodeErrorKind::Rejected
+    );
+    let error = node.add_block(build_block_on(&node, child_block_hash, 1, 6)).unwrap_err();
+    assert!(matches!(error, NodeError::InvalidAncestor { ancestor_hash, .. } if ancestor_hash == block_hash));
+    assert_eq!(error.kind(), NodeErrorKind::Rejected);
+}
+
+#[test]
+fn errors_describe_the_block_and_the_cause() {
+    let error = NodeError::InvalidAncestor {
+        block_hash: 1,
+        ancestor_hash: 0xabcdef,
+        ancestor_kind: NodeErrorKind::ConsensusInvalid,
+    };
+    assert_eq!(error.to_string(), "block 0x00000001: descends from invalid block 0x00abcdef");
+
+    let error = NodeError::InvalidTransaction {
+        block_hash: 2,
+        transaction_index: 1,
+        transaction_hash: 3,
+        error: TransactionError::InvalidBalance {
+            input_sum: 4,
+            output_sum: 5,
+        },
+    };
+    assert_eq!(
+        error.to_string(),
+        "block 0x00000002: transaction 1 (0x00000003) is invalid: outputs (5 units) exceed inputs (4 units)"
+    );
+    assert_eq!(
+        std::error::Error::source(&error).unwrap().to_string(),
+        "outputs (5 units) exceed inputs (4 units)"
+    );
+}
+
+#[test]
+fn duplicate_transactions_are_rejected() {
+    let mut node = Node::new(ChainParams::regtest());
+    let first_block = build_block(&node, 1, 1);
+    let coinbase_transaction = first_block.transactions[0].clone();
+
+    node.add_block(first_block).unwrap();
+
+    // The coinbase transaction has the same hash as the previous one, whose output is still unspent
+    let mut second_block = build_block(&node, 1, 2);
+    second_block.transactions[0] = coinbase_transaction;
+    seal_block(&mut second_block);
+
+    assert!(matches!(
+        node.add_block(second_block),
+        Err(NodeError::InvalidTransaction {
+            error: TransactionError::DuplicateTransaction,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn blocks_can_be_tested_before_being_mined() {
+    let node = Node::new(ChainParams::main());
+    let mut block = build_block(&node, 1, 1);
+
+    while block.header.has_valid_proof_of_work() {
+        block.header.nonce += 1;
+    }
+
+    assert!(matches!(
+        node.test_block_validity(&block, true),
+        Err(NodeError::InvalidDifficulty { .. })
+    ));
+
+    let report = node.test_block_validity(&block, false).unwrap();
+
+    assert_eq!(report.height, 1);
+    assert_eq!(report.reward, node.get_chain_params().block_value);
+    assert_eq!(report.created_outpoints, vec![(block.transactions[0].hash(), 0)]);
+    // Nothing has been added
+    assert_eq!(node.get_last_block_hash(), node.get_chain_params().genesis_block.hash());
+}
+
+#[test]
+fn invalid_transactions_are_not_accepted_in_the_pool() {
+    let mut node = Node::new(ChainParams::regtest());
+    let block = build_block(&node, 1, 1);
+    let coinbase_hash = block.transactions[0].hash();
+    let block_value = node.get_chain_params().block_value;
+
+    node.add_block(block).unwrap();
+
+    assert!(matches!(
+        node.add_transaction(spend(coinbase_hash, 2, 2, block_value)),
+        Err(MempoolError::InvalidTransaction {
+            error: TransactionError::InvalidInputSignature { input_index: 0 },
+            ..
+        })
+    ));
+    assert!(matches!(
+        node.add_transaction(spend(coinbase_hash, 1, 2, block_value + 1)),
+        Err(MempoolError::InvalidTransaction {
+            error: TransactionError::InvalidBalance { .. },
+            ..
+        })
+    ));
+    assert!(matches!(
+        node.add_transaction(spend(coinbase_hash + 1, 1, 2, 1)),
+        Err(MempoolError::OrphanTransaction { prev_transaction_hash, .. }) if prev_transaction_hash == coinbase_hash + 1
+    ));
+
+    // The spent transaction is known, but not the spent output, so there is nothing to wait for
+    let mut transaction = spend(coinbase_hash, 1, 2, 1);
+    transaction.inputs[0].output_index = 1;
+
+    assert!(matches!(
+        node.add_transaction(transaction),
+        Err(MempoolError::InvalidTransaction {
+            error: TransactionError::InvalidInputIndex { input_index: 0 },
+            ..
+        })
+    ));
+
+    // The transaction doesn't signal replaceability, so conflicting transactions are rejected
+    let mut transaction = spend(coinbase_hash, 1, 2, block_value - 1);
+    transaction.inputs[0].sequence = SEQUENCE_FINAL;
+    let transaction_hash = node.add_transaction(transaction.clone()).unwrap().transaction_hash;
+
+    assert!(matches!(
+        node.add_transaction(transaction),
+        Err(MempoolError::AlreadyInPool { .. })
+    ));
+    assert!(matches!(
+        node.add_transaction(spend(coinbase_hash, 1, 3, block_value)),
+        Err(MempoolError::Conflict {
+            input_index: 0,
+            conflicting_transaction_hash,
+            ..
+        }) if conflicting_transaction_hash == transaction_hash
+    ));
+    assert_eq!(node.get_awaiting_transactions().len(), 1);
+}
+
+#[test]
+fn evictions_raise_the_minimum_fee_rate_until_it_decays() {
+    let clock = MockClock::new(1_000);
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(clock.clone()));
+    let block_value = node.get_chain_params().block_value;
+    let mut coinbase_hashes = vec![];
+
+    for timestamp in 1..=4 {
+        let block = build_block(&node, 1, timestamp);
+
+        coinbase_hashes.push(block.transactions[0].hash());
+        node.add_block(block).unwrap();
+    }
+
+    let transaction_size = spend(coinbase_hashes[0], 1, 2, 0).get_size();
+
+    // Only one transaction fits in the pool
+    node.set_mempool_max_size(transaction_size);
+
+    let low_fee_hash = node.add_transaction(spend(coinbase_hashes[0], 1, 2, block_value - 1)).unwrap().transaction_hash;
+    let low_fee_rate = node.get_mempool().get(low_fee_hash).unwrap().fee_rate;
+    assert_eq!(node.get_mempool().get_min_fee_rate(1_000), MIN_RELAY_FEE_RATE);
+
+    let high_fee_hash = node.add_transaction(spend(coinbase_hashes[1], 1, 2, block_value - 10)).unwrap().transaction_hash;
+    assert!(!node.get_mempool().contains(low_fee_hash));
+    assert!(node.get_mempool().contains(high_fee_hash));
+
+    let min_fee_rate = node.get_mempool().get_min_fee_rate(1_000);
+    assert!(min_fee_rate > low_fee_rate);
+    assert!(matches!(
+        node.add_transaction(spend(coinbase_hashes[2], 1, 2, block_value - 1)),
+        Err(MempoolError::FeeTooLow { .. })
+    ));
+    // Enough to enter the pool, but less than the transaction already there
+    assert!(matches!(
+        node.add_transaction(spend(coinbase_hashes[2], 1, 2, block_value - 5)),
+        Err(MempoolError::MempoolFull { .. })
+    ));
+
+    // Evicting the new transaction raised the minimum fee rate again
+    let min_fee_rate = node.get_mempool().get_min_fee_rate(clock.now());
+
+    // The minimum fee rate is halved every half-life, down to the minimum relay fee rate
+    clock.advance(ROLLING_FEE_RATE_HALF_LIFE);
+    let decayed_min_fee_rate = node.get_mempool().get_min_fee_rate(clock.now());
+    assert_eq!(decayed_min_fee_rate, (min_fee_rate / 2).max(MIN_RELAY_FEE_RATE));
+
+    clock.advance(10 * ROLLING_FEE_RATE_HALF_LIFE);
+    assert_eq!(node.get_mempool().get_min_fee_rate(clock.now()), MIN_RELAY_FEE_RATE);
+}
+
+#[test]
+fn signaling_transactions_are_replaced_by_higher_fee_conflicts() {
+    let mut node = Node::new(ChainParams::regtest());
+    let block = build_block(&node, 1, 1);
+    let coinbase_transaction_hash = block.transactions[0].hash();
+
+    node.add_block(block).unwrap();
+
+    let original = spend(coinbase_transaction_hash, 1, 2, 95);
+    let child = spend(original.hash(), 2, 3, 90);
+
+    node.add_transaction(original.clone()).unwrap();
+    node.add_transaction(child.clone()).unwrap();
+
+    // The replacement must pay more than the original transaction and its child together
+    assert!(matches!(
+        node.add_transaction(spend(coinbase_transaction_hash, 1, 4, 92)),
+        Err(MempoolError::ReplacementFeeTooLow { .. })
+    ));
+
+    let mut replacement = spend(coinbase_transaction_hash, 1, 4, 80);
+
+    replacement.inputs[0].sequence = SEQUENCE_FINAL;
+
+    let acceptance = node.add_transaction(replacement.clone()).unwrap();
+
+    assert_eq!(acceptance.replaced_transaction_hashes, vec![original.hash(), child.hash()]);
+    assert!(!node.get_mempool().contains(original.hash()));
+    assert!(!node.get_mempool().contains(child.hash()));
+
+    // The replacement doesn't signal replaceability
+    assert!(matches!(
+        node.add_transaction(spend(coinbase_transaction_hash, 1, 5, 50)),
+        Err(MempoolError::Conflict { conflicting_transaction_hash, .. }) if conflicting_transaction_hash == replacement.hash()
+    ));
+}
+
+#[test]
+fn transactions_paid_for_by_their_children_are_evicted_last() {
+    let mut node = Node::new(ChainParams::regtest());
+    let first_block = build_block(&node, 1, 1);
+    let first_coinbase_transaction_hash = first_block.transactions[0].hash();
+
+    node.add_block(first_block).unwrap();
+
+    let second_block = build_block(&node, 1, 2);
+    let second_coinbase_transaction_hash = second_block.transactions[0].hash();
+
+    node.add_block(second_block).unwrap();
+
+    // The parent pays the lowest fee rate, but the child pays for both more than the other transaction pays on its own
+    let parent = spend(first_coinbase_transaction_hash, 1, 2, 99);
+    let child = spend(parent.hash(), 2, 3, 50);
+    let other = spend(second_coinbase_transaction_hash, 1, 4, 95);
+
+    node.add_transaction(parent.clone()).unwrap();
+    node.add_transaction(child.clone()).unwrap();
+    node.add_transaction(other.clone()).unwrap();
+
+    let transaction_size = node.get_mempool().get_size() / 3;
+
+    node.set_mempool_max_size(transaction_size * 2);
+
+    assert!(node.get_mempool().contains(parent.hash()));
+    assert!(node.get_mempool().contains(child.hash()));
+    assert!(!node.get_mempool().contains(other.hash()));
+}
+
+#[test]
+fn transactions_are_added_back_to_the_pool_after_a_reorg() {
+    let mut node = Node::new(ChainParams::regtest());
+    let block_value = node.get_chain_params().block_value;
+    let mut coinbase_hashes = vec![];
+
+    for timestamp in 1..=3 {
+        let block = build_block(&node, 1, timestamp);
+
+        coinbase_hashes.push(block.transactions[0].hash());
+        node.add_block(block).unwrap();
+    }
+
+    // A transaction paying a low fee rate is mined
+    let transaction = spend(coinbase_hashes[0], 1, 2, block_value - 1);
+    let transaction_hash = node.add_transaction(transaction.clone()).unwrap().transaction_hash;
+    let mut block = build_block(&node, 1, 4);
+
+    block.transactions[0].reward += 1;
+    block.transactions[0].outputs[0].value += 1;
+    block.transactions.push(transaction);
+    seal_block(&mut block);
+
+    let block_hash = node.add_block(block).unwrap();
+
+    assert!(node.get_mempool().is_empty());
+
+    // Then an eviction raises the minimum fee rate above it
+    let transaction_size = spend(coinbase_hashes[1], 1, 2, 0).get_size();
+
+    node.set_mempool_max_size(transaction_size);
+    node.add_transaction(spend(coinbase_hashes[1], 1, 2, block_value - 1)).unwrap();
+    node.add_transaction(spend(coinbase_hashes[2], 1, 2, block_value - 10)).unwrap();
+    node.set_mempool_max_size(10 * transaction_size);
+
+    assert!(matches!(
+        node.add_transaction(spend(coinbase_hashes[1], 1, 3, block_value - 1)),
+        Err(MempoolError::FeeTooLow { .. })
+    ));
+
+    node.invalidate_block(block_hash).unwrap();
+
+    assert!(node.get_mempool().contains(transaction_hash));
+    assert_eq!(node.get_mempool().len(), 2);
+}
+
+#[test]
+fn expired_transactions_are_removed_from_the_pool() {
+    let clock = MockClock::new(1_000);
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(clock.clone()));
+    let block_value = node.get_chain_params().block_value;
+    let mut coinbase_hashes = vec![];
+
+    for timestamp in 1..=2 {
+        let block = build_block(&node, 1, timestamp);
+
+        coinbase_hashes.push(block.transactions[0].hash());
+        node.add_block(block).unwrap();
+    }
+
+    node.set_mempool_expiry(100);
+
+    let parent = spend(coinbase_hashes[0], 1, 2, block_value - 1);
+    let child = spend(parent.hash(), 2, 3, block_value - 2);
+
+    node.add_transaction(parent.clone()).unwrap();
+    clock.advance(50);
+    node.add_transaction(child.clone()).unwrap();
+
+    // Exactly at the expiry, the parent is kept
+    clock.advance(50);
+    node.add_transaction(spend(coinbase_hashes[1], 1, 2, block_value - 1)).unwrap();
+    assert_eq!(node.get_mempool().len(), 3);
+
+    // Past it, the parent is removed with its child even though the child entered the pool later
+    clock.advance(1);
+    node.set_mempool_expiry(100);
+    assert_eq!(node.get_mempool().len(), 1);
+    assert!(!node.get_mempool().contains(parent.hash()));
+    assert!(!node.get_mempool().contains(child.hash()));
+}
+
+#[test]
+fn expiry_does_not_overflow_near_the_end_of_time() {
+    let clock = MockClock::new(1_000);
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(clock.clone()));
+    let block = build_block(&node, 1, 1);
+    let coinbase_transaction_hash = block.transactions[0].hash();
+
+    node.add_block(block).unwrap();
+
+    clock.set(u64::MAX - 10);
+
+    let transaction_hash = node.add_transaction(spend(coinbase_transaction_hash, 1, 2, 90)).unwrap().transaction_hash;
+
+    node.set_mempool_expiry(u64::MAX);
+    assert!(node.get_mempool().contains(transaction_hash));
+
+    clock.set(u64::MAX);
+    node.set_mempool_expiry(5);
+    assert!(!node.get_mempool().contains(transaction_hash));
+}
+
+#[test]
+fn pool_evictions_happen_at_the_current_time() {
+    let data_dir = std::env::temp_dir().join("vitecoin-test-pool-evictions-happen-at-the-current-time");
+    let clock = MockClock::new(1_700_000_000);
+    let mut node = Node::with_clock(ChainParams::regtest(), Box::new(clock.clone()));
+    let block_value = node.get_chain_params().block_value;
+    let mut blocks = vec![];
+    let mut transactions = vec![];
+
+    for (timestamp, fee) in [(1, 5), (2, 10)] {
+        let block = build_block(&node, 1, timestamp);
+
+        transactions.push(spend(block.transactions[0].hash(), 1, 2, block_value - fee));
+        node.add_block(block.clone()).unwrap();
+        blocks.push(block);
+    }
+
+    for transaction in transactions {
+        node.add_transaction(transaction).unwrap();
+    }
+
+    let transaction_size = node.get_mempool().get_size() / 2;
+
+    std::fs::create_dir_all(&data_dir).unwrap();
+    node.save_mempool(&data_dir).unwrap();
+
+    // The transactions are loaded days after they entered the pool, into a pool that can only keep one of them
+    let restart_time = 1_700_000_000 + 3 * 24 * 60 * 60;
+
+    clock.set(restart_time);
+
+    let mut restarted_node = Node::with_clock(ChainParams::regtest(), Box::new(clock.clone()));
+
+    for block in blocks {
+        restarted_node.add_block(block).unwrap();
+    }
+
+    restarted_node.set_mempool_max_size(transaction_size);
+    restarted_node.load_mempool(&data_dir).unwrap();
+    std::fs::remove_dir_all(&data_dir).unwrap();
+
+    // The minimum fee rate raised by the eviction has not decayed yet
+    assert_eq!(restarted_node.get_mempool().len(), 1);
+    assert!(restarted_node.get_mempool().get_min_fee_rate(restart_time) > MIN_RELAY_FEE_RATE);
+}
+
+#[test]
+fn malformed_mempool_files_are_rejected() {
+    let data_dir = std::env::temp_dir().join("vitecoin-test-malformed-mempool-files-are-rejected");
+    let mut node = Node::new(ChainParams::regtest());
+    let block = build_block(&node, 1, 1);
+    let coinbase_transaction_hash = block.transactions[0].hash();
+
+    node.add_block(block).unwrap();
+    node.add_transaction(spend(coinbase_transaction_hash, 1, 2, 90)).unwrap();
+
+    std::fs::create_dir_all(&data_dir).unwrap();
+    node.save_mempool(&data_dir).unwrap();
+
+    let file_path = data_dir.join(MEMPOOL_FILE_NAME);
+    let bytes = std::fs::read(&file_path).unwrap();
+    let mut results = vec![];
+
+    // Truncated, with trailing bytes, with an unknown version
+    for malformed_bytes in [bytes[..bytes.len() - 1].to_vec(), [bytes.clone(), vec![0]].concat(), [vec![0xFF; 4], bytes[4..].to_vec()].concat()] {
+        let mut restarted_node = Node::new(ChainParams::regtest());
+
+        std::fs::write(&file_path, malformed_bytes).unwrap();
+        results.push(restarted_node.load_mempool(&data_dir).map_err(|error| error.kind()));
+        assert!(restarted_node.get_mempool().is_empty());
+    }
+
+    std::fs::remove_dir_all(&data_dir).unwrap();
+
+    assert!(results.iter().all(|result| *result == Err(std::io::ErrorKind::InvalidData)));
+}
+
+#[test]
+fn orphan_transactions_are_added_when_their_parents_arrive() {
+    let mut node = Node::new(ChainParams::regtest());
+    let block_value = node.get_chain_params().block_value;
+    let mut coinbase_hashes = vec![];
+
+    for timestamp in 1..=2 {
+        let block = build_block(&node, 1, timestamp);
+
+        coinbase_hashes.push(block.transactions[0].hash());
+        node.add_block(block).unwrap();
+    }
+
+    // Received before its parent, then a grandchild received before the child
+    let parent = spend(coinbase_hashes[0], 1, 2, block_value - 1);
+    let child = spend(parent.hash(), 2, 3, block_value - 2);
+    let grandchild = spend(child.hash(), 3, 4, block_value - 3);
+
+    for transaction in [&grandchild, &child] {
+        assert!(matches!(
+            node.add_peer_transaction(transaction.clone(), 1),
+            Err(MempoolError::OrphanTransaction { .. })
+        ));
+    }
+
+    assert_eq!(node.get_orphan_transactions().len(), 2);
+
+    node.add_transaction(parent.clone()).unwrap();
+
+    assert_eq!(node.get_orphan_transactions().len(), 0);
+    assert!(node.get_mempool().contains(child.hash()));
+    assert!(node.get_mempool().contains(grandchild.hash()));
+
+    // The parent may also arrive in a block
+    let block_parent = spend(coinbase_hashes[1], 1, 2, block_value);
+    let block_child = spend(block_parent.hash(), 2, 3, block_value - 1);
+    let mut block = build_block(&node, 1, 3);
+
+    assert!(matches!(
+        node.add_transaction(block_child.clone()),
+        Err(MempoolError::OrphanTransaction { .. })
+    ));
+
+    block.transactions.push(block_parent);
+    seal_block(&mut block);
+    node.add_block(block).unwrap();
+
+    assert_eq!(node.get_orphan_transactions().len(), 0);
+    assert!(node.get_mempool().contains(block_child.hash()));
+}
+
+#[test]
+fn orphan_transactions_are_limited_per_peer() {
+    let mut node = Node::new(ChainParams::regtest());
+    let orphan = |missing_hash: u32| spend(missing_hash, 1, 2, 1);
+
+    // The oldest orphan of the peer is evicted to make room for its new ones
+    for missing_hash in 0..=MAX_ORPHAN_TRANSACTIONS_PER_PEER as u32 {
+        assert!(matches!(
+            node.add_peer_transaction(orphan(missing_hash), 1),
+            Err(MempoolError::OrphanTransaction { .. })
+        ));
+    }
+
+    assert_eq!(node.get_orphan_transactions().len(), MAX_ORPHAN_TRANSACTIONS_PER_PEER);
+    assert!(!node.get_orphan_transactions().contains(orphan(0).hash()));
+    assert!(node.get_orphan_transactions().contains(orphan(1).hash()));
+
+    // Other peers and local transactions are not affected
+    node.add_peer_transaction(orphan(1_000), 2).unwrap_err();
+    node.add_transaction(orphan(1_001)).unwrap_err();
+
+    assert_eq!(node.get_orphan_transactions().len(), MAX_ORPHAN_TRANSACTIONS_PER_PEER + 2);
+
+    assert_eq!(node.remove_peer_orphan_transactions(1), MAX_ORPHAN_TRANSACTIONS_PER_PEER);
+    assert_eq!(node.remove_peer_orphan_transactions(1), 0);
+    assert_eq!(node.get_orphan_transactions().len(), 2);
+    assert!(node.get_orphan_transactions().contains(orphan(1_000).hash()));
+    assert!(node.get_orphan_transactions().contains(orphan(1_001).hash()));
+}
+
+#[test]
+fn oldest_orphan_transactions_are_evicted() {
+    let mut node = Node::new(ChainParams::regtest());
+    let orphan = |missing_hash: u32| spend(missing_hash, 1, 2, 1);
+
+    for missing_hash in 0..=MAX_ORPHAN_TRANSACTIONS as u32 {
+        let peer_id = missing_hash % (MAX_ORPHAN_TRANSACTIONS / MAX_ORPHAN_TRANSACTIONS_PER_PEER + 1) as u32;
+
+        node.add_peer_transaction(orphan(missing_hash), peer_id).unwrap_err();
+    }
+
+    assert_eq!(node.get_orphan_transactions().len(), MAX_ORPHAN_TRANSACTIONS);
+    assert!(!node.get_orphan_transactions().contains(orphan(0).hash()));
+    assert!(node.get_orphan_transactions().contains(orphan(MAX_ORPHAN_TRANSACTIONS as u32).hash()));
+}
+
+#[test]
+fn transactions_can_be_tested_without_entering_the_pool() {
+    let mut node = Node::new(ChainParams::regtest());
+    let block_value = node.get_chain_params().block_value;
+    let mut coinbase_hashes = vec![];
+
+    for timestamp in 1..=2 {
+        let block = build_block(&node, 1, timestamp);
+
+        coinbase_hashes.push(block.transactions[0].hash());
+        node.add_block(block).unwrap();
+    }
+
+    let pool_transaction = spend(coinbase_hashes[1], 1, 2, block_value - 1);
+
+    node.add_transaction(pool_transaction.clone()).unwrap();
+
+    // The child spends the output of its parent, tested just before it
+    let parent = spend(coinbase_hashes[0], 1, 2, block_value - 10);
+    let child = spend(parent.hash(), 2, 3, block_value - 20);
+    let conflicting = spend(coinbase_hashes[0], 1, 4, block_value - 5);
+    let results = node.test_mempool_accept(&[parent.clone(), child.clone(), conflicting]);
+
+    assert_eq!(results.len(), 3);
+
+    let parent_acceptance = results[0].as_ref().unwrap();
+    let child_acceptance = results[1].as_ref().unwrap();
+
+    assert_eq!(parent_acceptance.transaction_hash, parent.hash());
+    assert_eq!(parent_acceptance.fee, 10);
+    assert_eq!(parent_acceptance.size, parent.get_size());
+    assert_eq!(child_acceptance.fee, 10);
+    assert!(child_acceptance.replaced_transaction_hashes.is_empty());
+    // Paying a lower fee rate than the parent, the conflicting transaction can't replace it
+    assert!(matches!(results[2], Err(MempoolError::ReplacementFeeRateTooLow { .. })));
+
+    // Tested alone, the child spends an unknown transaction
+    assert!(matches!(
+        node.test_mempool_accept(&[child])[0],
+        Err(MempoolError::InvalidTransaction {
+            error: TransactionError::InvalidInputHash { .. },
+            ..
+        })
+    ));
+
+    // Nothing changed in the pools
+    assert_eq!(node.get_mempool().len(), 1);
+    assert!(node.get_mempool().contains(pool_transaction.hash()));
+    assert_eq!(node.get_orphan_transactions().len(), 0);
+
+    node.add_transaction(parent).unwrap();
+}
+
+#[test]
+fn packages_are_accepted_by_their_overall_fee_rate() {
+    let mut node = Node::new(ChainParams::regtest());
+    let block = build_block(&node, 1, 1);
+    let coinbase_transaction_hash = block.transactions[0].hash();
+    let block_value = node.get_chain_params().block_value;
+
+    node.add_block(block).unwrap();
+
+    // The parent pays no fee, so it can't enter the pool on its own
+    let parent = spend(coinbase_transaction_hash, 1, 2, block_value);
+    let child = spend(parent.hash(), 2, 3, block_value - 10);
+
+    assert!(matches!(
+        node.add_transaction(parent.clone()),
+        Err(MempoolError::FeeTooLow { .. })
+    ));
+    assert!(matches!(
+        node.add_package(vec![parent.clone(), spend(parent.hash(), 2, 3, block_value)]),
+        Err(MempoolError::PackageFeeTooLow { .. })
+    ));
+    assert_eq!(node.get_mempool().len(), 0);
+
+    let acceptances = node.add_package(vec![parent.clone(), child.clone()]).unwrap();
+
+    assert_eq!(acceptances.len(), 2);
+    assert_eq!(acceptances[0].fee, 0);
+    assert_eq!(acceptances[1].fee, 10);
+    assert!(node.get_mempool().contains(parent.hash()));
+    assert!(node.get_mempool().contains(child.hash()));
+}
+
+#[test]
+fn packages_must_be_a_child_with_its_parents() {
+    let mut node = Node::new(ChainParams::regtest());
+    let block = build_block(&node, 1, 1);
+    let coinbase_transaction_hash = block.transactions[0].hash();
+
+    node.add_block(block).unwrap();
+
+    let parent = spend(coinbase_transaction_hash, 1, 2, 100);
+    let child = spend(parent.hash(), 2, 3, 90);
+    let grandchild = spend(child.hash(), 3, 4, 80);
+
+    // The parent is not spent by the grandchild
+    assert!(matches!(
+        node.add_package(vec![parent.clone(), child.clone(), grandchild]),
+        Err(MempoolError::PackageNotChildWithParents { transaction_hash }) if transaction_hash == parent.hash()
+    ));
+    assert_eq!(node.get_mempool().len(), 0);
+
+    node.add_package(vec![parent, child]).unwrap();
+
+    assert_eq!(node.get_mempool().len(), 2);
+}
+
+#[test]
+fn packages_are_added_back_to_the_pool_after_a_reorg() {
+    let mut node = Node::new(ChainParams::regtest());
+    let block = build_block(&node, 1, 1);
+    let coinbase_transaction_hash = block.transactions[0].hash();
+
+    node.add_block(block).unwrap();
+
+    // The parent pays no fee, the child pays for both
+    let parent = spend(coinbase_transaction_hash, 1, 2, 100);
+    let child = spend(parent.hash(), 2, 3, 90);
+
+    node.add_package(vec![parent.clone(), child.clone()]).unwrap();
+
+    let mut block = build_block(&node, 1, 2);
+
+    block.transactions[0].reward += 10;
+    block.transactions[0].outputs[0].value += 10;
+    block.transactions.extend([parent.clone(), child.clone()]);
+    seal_block(&mut block);
+
+    let block_hash = node.add_block(block).unwrap();
+
+    assert_eq!(node.get_mempool().len(), 0);
+
+    node.invalidate_block(block_hash).unwrap();
+
+    assert!(node.get_mempool().contains(parent.hash()));
+    assert!(node.get_mempool().contains(child.hash()));
+}
+
+#[test]
+fn packages_survive_a_restart() {
+    let data_dir = std::env::temp_dir().join("vitecoin-test-packages-survive-a-restart");
+    let mut node = Node::new(ChainParams::regtest());
+    let block = build_block(&node, 1, 1);
+    let coinbase_transaction_hash = block.transactions[0].hash();
+
+    node.add_block(block.clone()).unwrap();
+
+    // The parent pays no fee, the child pays for both
+    let parent = spend(coinbase_transaction_hash, 1, 2, 100);
+    let child = spend(parent.hash(), 2, 3, 90);
+
+    node.add_package(vec![parent, child]).unwrap();
+    std::fs::create_dir_all(&data_dir).unwrap();
+    node.save_mempool(&data_dir).unwrap();
+
+    let mut restarted_node = Node::new(ChainParams::regtest());
+
+    restarted_node.add_block(block).unwrap();
+
+    let counts = restarted_node.load_mempool(&data_dir).unwrap();
+
+    std::fs::remove_dir_all(&data_dir).unwrap();
+    assert_eq!(counts, (2, 0));
+}
+
+#[test]
+fn block_templates_are_valid_before_mining() {
+    let mut node = Node::new(ChainParams::main());
+    let block = build_block(&node, 1, 1);
+    let coinbase_transaction_hash = block.transactions[0].hash();
+    let block_value = node.get_chain_params().block_value;
+
+    node.add_block(block).unwrap();
+
+    let parent = spend(coinbase_transaction_hash, 1, 2, block_value - 5);
+    let child = spend(parent.hash(), 2, 3, block_value - 15);
+
+    node.add_transaction(parent.clone()).unwrap();
+    node.add_transaction(child.clone()).unwrap();
+
+    let template = node.create_block_template(vec![(4, 1)]).unwrap();
+
+    assert_eq!(template.height, 2);
+    assert_eq!(template.fees, 15);
+    assert_eq!(template.reward, block_value + 15);
+    let transaction_hashes: Vec<u32> = template.block.transactions[1..]
+        .iter()
+        .map(|transaction| transaction.hash())
+        .collect();
+
+    assert_eq!(transaction_hashes, vec![parent.hash(), child.hash()]);
+    assert_eq!(template.block.transactions[0].outputs[0].value, template.reward);
+    assert!(template.block.header.difficulty_target > 0);
+
+    // Everything but the proof of work is valid
+    let report = node.test_block_validity(&template.block, false).unwrap();
+
+    assert_eq!(report.height, 2);
+    assert_eq!(report.reward, template.reward);
+
+    let mut block = template.block;
+
+    seal_block(&mut block);
+    node.add_block(block).unwrap();
+
+    assert!(node.get_mempool().is_empty());
+}
+
+#[test]
+fn block_templates_split_the_reward_by_weight() {
+    let node = Node::new(ChainParams::regtest());
+    let get_values = |payouts: Vec<(u32, u64)>| -> Vec<u64> {
+        node.create_block_template(payouts).unwrap().block.transactions[0]
+            .outputs
+            .iter()
+            .map(|output| output.value)
+            .collect()
+    };
+
+    assert!(matches!(
+        node.create_block_template(vec![]),
+        Err(BlockTemplateError::NoPayouts)
+    ));
+
+    assert_eq!(get_values(vec![(1, 0)]), vec![100]);
+    assert_eq!(get_values(vec![(1, 1), (2, 3)]), vec![25, 75]);
+    // What is lost when rounding goes to the last recipient
+    assert_eq!(get_values(vec![(1, 1), (2, 1), (3, 1)]), vec![33, 33, 34]);
+    assert_eq!(get_values(vec![(1, 0), (2, 0)]), vec![0, 100]);
+    assert_eq!(get_values(vec![(1, u64::MAX), (2, u64::MAX)]), vec![50, 50]);
+}
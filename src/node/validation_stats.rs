@@ -0,0 +1,8 @@
+// Counters describing how much validation has been skipped thanks to the assume-valid block
+#[derive(Debug, Clone, Default)]
+pub struct ValidationStats {
+    pub connected_blocks: u64,
+    pub assumed_valid_blocks: u64, // Blocks whose signatures have not been checked
+    pub checked_signatures: u64,
+    pub skipped_signatures: u64,
+}
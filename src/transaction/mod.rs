@@ -1,4 +1,10 @@
+pub mod spent_output;
 pub mod transaction;
+pub mod transaction_error;
 pub mod transaction_input;
 pub mod transaction_output;
-pub mod unspent_transaction;
\ No newline at end of file
+pub mod unspent_outputs_overlay;
+pub mod unspent_transaction;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
@@ -0,0 +1,11 @@
+use super::transaction_output::TransactionOutput;
+
+// Output spent by a block, kept so it can be restored if the block is disconnected from the chain
+#[derive(Clone)]
+pub struct SpentOutput {
+    pub transaction_hash: u32,
+    pub output_index: u32,
+    pub output: TransactionOutput,
+    pub height: u32,
+    pub median_time_past: u64,
+}
@@ -0,0 +1,69 @@
+use super::{transaction::Transaction, transaction_input::TransactionInput, transaction_output::TransactionOutput};
+use crate::constants::{
+    LOCKTIME_THRESHOLD, SEQUENCE_FINAL, SEQUENCE_LOCKTIME_DISABLE_FLAG, SEQUENCE_LOCKTIME_TYPE_FLAG,
+};
+
+fn build_transaction(version: u32, locktime: u32, sequence: u32) -> Transaction {
+    Transaction {
+        version,
+        reward: 0,
+        inputs: vec![TransactionInput {
+            prev_transaction_hash: 1,
+            output_index: 0,
+            signature: 1,
+            sequence,
+        }],
+        outputs: vec![TransactionOutput {
+            recipient_public_key: 2,
+            value: 10,
+        }],
+        locktime,
+        coinbase_data: vec![],
+    }
+}
+
+#[test]
+fn height_locktime_is_final_after_its_height() {
+    let transaction = build_transaction(1, 10, 0);
+
+    assert!(!transaction.is_final(10, 0));
+    assert!(transaction.is_final(11, 0));
+    assert!(build_transaction(1, 0, 0).is_final(0, 0));
+}
+
+#[test]
+fn time_locktime_is_final_after_the_median_time_past() {
+    let locktime = LOCKTIME_THRESHOLD + 1000;
+    let transaction = build_transaction(1, locktime, 0);
+
+    // The height is not compared to a time based locktime
+    assert!(!transaction.is_final(u32::MAX, locktime as u64));
+    assert!(transaction.is_final(0, locktime as u64 + 1));
+}
+
+#[test]
+fn final_sequences_disable_the_locktime() {
+    assert!(build_transaction(1, 10, SEQUENCE_FINAL).is_final(5, 0));
+    assert!(!build_transaction(1, 10, SEQUENCE_FINAL - 1).is_final(5, 0));
+}
+
+#[test]
+fn height_relative_locktime_needs_version_2() {
+    // The spent output is in the block at height 5, the input waits for 10 blocks
+    let input_coins = [(5, 0)];
+
+    assert!(build_transaction(1, 0, 10).check_sequence_locks(&input_coins, 6, 0));
+    assert!(!build_transaction(2, 0, 10).check_sequence_locks(&input_coins, 14, 0));
+    assert!(build_transaction(2, 0, 10).check_sequence_locks(&input_coins, 15, 0));
+    assert!(build_transaction(2, 0, SEQUENCE_LOCKTIME_DISABLE_FLAG | 10).check_sequence_locks(&input_coins, 6, 0));
+}
+
+#[test]
+fn time_relative_locktime_has_a_512_second_granularity() {
+    // The input waits for 2 * 512 seconds after the median time past before the spent output
+    let transaction = build_transaction(2, 0, SEQUENCE_LOCKTIME_TYPE_FLAG | 2);
+    let input_coins = [(5, 1000)];
+
+    assert!(!transaction.check_sequence_locks(&input_coins, 100, 2023));
+    assert!(transaction.check_sequence_locks(&input_coins, 100, 2024));
+}
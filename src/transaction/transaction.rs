@@ -1,4 +1,16 @@
 use super::{transaction_input::TransactionInput, transaction_output::TransactionOutput};
+use crate::{
+    constants::{
+        LOCKTIME_THRESHOLD, MAX_BIP125_RBF_SEQUENCE, RELATIVE_LOCKTIME_VERSION, SEQUENCE_FINAL,
+        SEQUENCE_LOCKTIME_DISABLE_FLAG, SEQUENCE_LOCKTIME_GRANULARITY, SEQUENCE_LOCKTIME_MASK,
+        SEQUENCE_LOCKTIME_TYPE_FLAG,
+    },
+    utils::{
+        deserializer::{Deserializable, Deserializer},
+        hasher::Hasher,
+        serializer::{Serializable, Serializer},
+    },
+};
 
 #[derive(Clone)]
 pub struct Transaction {
@@ -6,12 +18,102 @@ pub struct Transaction {
     pub reward: u64, // Only used in the very first transaction of each block, should include the block value + the sum of all transaction fees in the block
     pub inputs: Vec<TransactionInput>,
     pub outputs: Vec<TransactionOutput>,
-    pub locktime: u32,
+    pub locktime: u32, // Block height or timestamp (depending on `LOCKTIME_THRESHOLD`) before which the transaction cannot be included in a block
+    pub coinbase_data: Vec<u8>, // Arbitrary data (message, extra nonce...), must be empty for non-coinbase transactions
 }
 
 impl Transaction {
     pub fn hash(&self) -> u32 {
-        // Dummy hash function. Ww return the `locktime` field, which in reality is used for something else entirely.
-        self.locktime
+        let mut hasher = Hasher::new();
+
+        hasher.write(&self.to_bytes());
+        hasher.finish()
+    }
+
+    // Whether the transaction accepts to be replaced by a conflicting one paying a higher fee while it's in the pool (BIP125)
+    pub fn signals_replaceability(&self) -> bool {
+        self.inputs
+            .iter()
+            .any(|input| input.sequence <= MAX_BIP125_RBF_SEQUENCE)
+    }
+
+    // Checks the absolute locktime of the transaction against the block it would be included in.
+    // `block_time` is the median time past of the previous block.
+    pub fn is_final(&self, block_height: u32, block_time: u64) -> bool {
+        if self.locktime == 0 {
+            return true;
+        }
+
+        let limit = match self.locktime < LOCKTIME_THRESHOLD {
+            true => block_height as u64,
+            false => block_time,
+        };
+
+        if (self.locktime as u64) < limit {
+            return true;
+        }
+
+        // The locktime is ignored if every input opts out of it
+        self.inputs
+            .iter()
+            .all(|input| input.sequence == SEQUENCE_FINAL)
+    }
+
+    // Checks the relative locktimes of the transaction inputs against the block it would be included in.
+    // `input_coins` contains, for each input, the height of the block containing the spent output and the
+    // median time past of the block before it. `block_time` is the median time past of the previous block.
+    pub fn check_sequence_locks(
+        &self,
+        input_coins: &[(u32, u64)],
+        block_height: u32,
+        block_time: u64,
+    ) -> bool {
+        if self.version < RELATIVE_LOCKTIME_VERSION {
+            return true;
+        }
+
+        for (input, (coin_height, coin_time)) in self.inputs.iter().zip(input_coins) {
+            if input.sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+                continue;
+            }
+
+            let value = input.sequence & SEQUENCE_LOCKTIME_MASK;
+
+            if input.sequence & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+                let min_time = coin_time + ((value as u64) << SEQUENCE_LOCKTIME_GRANULARITY);
+
+                if min_time > block_time {
+                    return false;
+                }
+            } else if *coin_height as u64 + value as u64 > block_height as u64 {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Serializable for Transaction {
+    fn serialize(&self, serializer: &mut Serializer) {
+        serializer.write_u32(self.version);
+        serializer.write_u64(self.reward);
+        serializer.write_list(&self.inputs);
+        serializer.write_list(&self.outputs);
+        serializer.write_u32(self.locktime);
+        serializer.write_bytes(&self.coinbase_data);
+    }
+}
+
+impl Deserializable for Transaction {
+    fn deserialize(deserializer: &mut Deserializer) -> Option<Self> {
+        Some(Self {
+            version: deserializer.read_u32()?,
+            reward: deserializer.read_u64()?,
+            inputs: deserializer.read_list()?,
+            outputs: deserializer.read_list()?,
+            locktime: deserializer.read_u32()?,
+            coinbase_data: deserializer.read_bytes()?,
+        })
     }
 }
@@ -0,0 +1,49 @@
+use std::fmt;
+
+// Reason why a transaction is invalid, independently of where it comes from (block or transaction pool)
+#[derive(Debug, Clone)]
+pub enum TransactionError {
+    EmptyInputs,
+    EmptyOutputs,
+    InvalidInputHash { input_index: usize }, // The spent transaction has no unspent output
+    InvalidInputIndex { input_index: usize }, // The spent output doesn't exist or has already been spent
+    InvalidInputSignature { input_index: usize },
+    InvalidBalance { input_sum: u64, output_sum: u64 },
+    InvalidReward, // Only coinbase transactions can have a reward
+    InvalidCoinbaseData,
+    NonFinal { locktime: u32 },
+    SequenceLocksNotSatisfied,
+    DuplicateTransaction, // A transaction with the same hash still has unspent outputs, which would be overwritten
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EmptyInputs => write!(f, "no inputs"),
+            Self::EmptyOutputs => write!(f, "no outputs"),
+            Self::InvalidInputHash { input_index } => {
+                write!(f, "input {} spends an unknown transaction", input_index)
+            }
+            Self::InvalidInputIndex { input_index } => {
+                write!(f, "input {} spends an unknown or already spent output", input_index)
+            }
+            Self::InvalidInputSignature { input_index } => {
+                write!(f, "input {} has an invalid signature", input_index)
+            }
+            Self::InvalidBalance { input_sum, output_sum } => write!(
+                f,
+                "outputs ({} units) exceed inputs ({} units)",
+                output_sum, input_sum
+            ),
+            Self::InvalidReward => write!(f, "only coinbase transactions can have a reward"),
+            Self::InvalidCoinbaseData => write!(f, "invalid coinbase data"),
+            Self::NonFinal { locktime } => write!(f, "not final (locktime {})", locktime),
+            Self::SequenceLocksNotSatisfied => write!(f, "relative locktime not satisfied"),
+            Self::DuplicateTransaction => {
+                write!(f, "a transaction with the same hash still has unspent outputs")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
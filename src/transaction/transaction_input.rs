@@ -1,7 +1,32 @@
+use crate::utils::{
+    deserializer::{Deserializable, Deserializer},
+    serializer::{Serializable, Serializer},
+};
+
 #[derive(Clone)]
 pub struct TransactionInput {
     pub prev_transaction_hash: u32,
     pub output_index: u32,
     pub signature: u32,
     pub sequence: u32,
-}
\ No newline at end of file
+}
+
+impl Serializable for TransactionInput {
+    fn serialize(&self, serializer: &mut Serializer) {
+        serializer.write_u32(self.prev_transaction_hash);
+        serializer.write_u32(self.output_index);
+        serializer.write_u32(self.signature);
+        serializer.write_u32(self.sequence);
+    }
+}
+
+impl Deserializable for TransactionInput {
+    fn deserialize(deserializer: &mut Deserializer) -> Option<Self> {
+        Some(Self {
+            prev_transaction_hash: deserializer.read_u32()?,
+            output_index: deserializer.read_u32()?,
+            signature: deserializer.read_u32()?,
+            sequence: deserializer.read_u32()?,
+        })
+    }
+}
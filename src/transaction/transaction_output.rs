@@ -1,5 +1,26 @@
+use crate::utils::{
+    deserializer::{Deserializable, Deserializer},
+    serializer::{Serializable, Serializer},
+};
+
 #[derive(Clone)]
 pub struct TransactionOutput {
     pub value: u64,
     pub recipient_public_key: u32,
-}
\ No newline at end of file
+}
+
+impl Serializable for TransactionOutput {
+    fn serialize(&self, serializer: &mut Serializer) {
+        serializer.write_u64(self.value);
+        serializer.write_u32(self.recipient_public_key);
+    }
+}
+
+impl Deserializable for TransactionOutput {
+    fn deserialize(deserializer: &mut Deserializer) -> Option<Self> {
+        Some(Self {
+            value: deserializer.read_u64()?,
+            recipient_public_key: deserializer.read_u32()?,
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use super::{transaction_output::TransactionOutput, unspent_transaction::UnspentTransaction};
+use std::collections::{HashMap, HashSet};
+
+// Records outputs created and spent on top of the unspent transactions, without modifying them.
+// Outputs are identified by the hash of their transaction and their index.
+pub struct UnspentOutputsOverlay<'a> {
+    unspent_transactions: &'a HashMap<u32, UnspentTransaction>,
+    added_outputs: HashMap<(u32, u32), (TransactionOutput, u32, u64)>, // Output, height and median time past
+    spent_outputs: HashSet<(u32, u32)>,
+}
+
+impl<'a> UnspentOutputsOverlay<'a> {
+    pub fn new(unspent_transactions: &'a HashMap<u32, UnspentTransaction>) -> Self {
+        Self {
+            unspent_transactions,
+            added_outputs: HashMap::new(),
+            spent_outputs: HashSet::new(),
+        }
+    }
+
+    // Whether the transaction has unspent outputs, or had some before being spent in the overlay
+    pub fn contains_transaction(&self, transaction_hash: u32) -> bool {
+        self.unspent_transactions.contains_key(&transaction_hash)
+            || self
+                .added_outputs
+                .keys()
+                .chain(&self.spent_outputs)
+                .any(|(hash, _)| *hash == transaction_hash)
+    }
+
+    // Whether some outputs of the transaction are still unspent
+    pub fn has_unspent_outputs(&self, transaction_hash: u32) -> bool {
+        self.added_outputs.keys().any(|(hash, _)| *hash == transaction_hash)
+            || self
+                .unspent_transactions
+                .get(&transaction_hash)
+                .is_some_and(|transaction| {
+                    transaction
+                        .unspent_outputs
+                        .keys()
+                        .any(|output_index| !self.spent_outputs.contains(&(transaction_hash, *output_index)))
+                })
+    }
+
+    // Returns the output with the height and median time past of its transaction, if it's unspent
+    pub fn get(&self, transaction_hash: u32, output_index: u32) -> Option<(&TransactionOutput, u32, u64)> {
+        if let Some((output, height, median_time_past)) = self.added_outputs.get(&(transaction_hash, output_index)) {
+            return Some((output, *height, *median_time_past));
+        }
+
+        if self.spent_outputs.contains(&(transaction_hash, output_index)) {
+            return None;
+        }
+
+        let transaction = self.unspent_transactions.get(&transaction_hash)?;
+        let output = transaction.unspent_outputs.get(&output_index)?;
+
+        Some((output, transaction.height, transaction.median_time_past))
+    }
+
+    pub fn add(&mut self, transaction_hash: u32, output_index: u32, output: TransactionOutput, height: u32, median_time_past: u64) {
+        self.added_outputs
+            .insert((transaction_hash, output_index), (output, height, median_time_past));
+    }
+
+    // Returns false if the output was already spent or never existed
+    pub fn spend(&mut self, transaction_hash: u32, output_index: u32) -> bool {
+        if self.added_outputs.remove(&(transaction_hash, output_index)).is_some() {
+            return true;
+        }
+
+        self.get(transaction_hash, output_index).is_some()
+            && self.spent_outputs.insert((transaction_hash, output_index))
+    }
+}
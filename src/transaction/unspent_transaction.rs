@@ -4,13 +4,17 @@ use std::collections::HashMap;
 #[derive(Clone)]
 pub struct UnspentTransaction {
     pub hash: u32,
+    pub height: u32,           // Height of the block containing the transaction
+    pub median_time_past: u64, // Median time past of the block before the one containing the transaction
     pub unspent_outputs: HashMap<u32, TransactionOutput>,
 }
 
 impl UnspentTransaction {
-    pub fn new(hash: u32) -> Self {
+    pub fn new(hash: u32, height: u32, median_time_past: u64) -> Self {
         Self {
             hash,
+            height,
+            median_time_past,
             unspent_outputs: HashMap::new(),
         }
     }
@@ -0,0 +1,47 @@
+use std::{cell::Cell, rc::Rc, time::SystemTime};
+
+// Source of the current time (in seconds since the Unix epoch) used by the node to validate block timestamps.
+// It is abstracted so that the node can be run against a simulated time, e.g. past the year 2106.
+pub trait Clock {
+    fn now(&self) -> u64;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+}
+
+// Clock whose time is set manually. Clones share the same time, so one can be given to the node
+// while another is kept to move the time forward.
+#[derive(Clone)]
+pub struct MockClock {
+    time: Rc<Cell<u64>>,
+}
+
+impl MockClock {
+    pub fn new(time: u64) -> Self {
+        Self {
+            time: Rc::new(Cell::new(time)),
+        }
+    }
+
+    pub fn set(&self, time: u64) {
+        self.time.set(time);
+    }
+
+    pub fn advance(&self, secs: u64) {
+        self.time.set(self.time.get() + secs);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> u64 {
+        self.time.get()
+    }
+}
@@ -0,0 +1,70 @@
+// Decodes the canonical binary encoding produced by `Serializer`.
+// Reading past the end of the bytes returns `None`.
+pub struct Deserializer<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Deserializer<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub fn read_u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_bytes(&mut self) -> Option<Vec<u8>> {
+        let len = self.read_u32()? as usize;
+
+        Some(self.read_slice(len)?.to_vec())
+    }
+
+    pub fn read_list<T: Deserializable>(&mut self) -> Option<Vec<T>> {
+        let len = self.read_u32()? as usize;
+        // The length comes from untrusted data, so it can't be used to allocate directly
+        let mut list = Vec::with_capacity(len.min(self.bytes.len() - self.position));
+
+        for _ in 0..len {
+            list.push(T::deserialize(self)?);
+        }
+
+        Some(list)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.read_slice(N)?.try_into().ok()
+    }
+
+    fn read_slice(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.position.checked_add(len)?;
+        let slice = self.bytes.get(self.position..end)?;
+
+        self.position = end;
+
+        Some(slice)
+    }
+}
+
+pub trait Deserializable: Sized {
+    fn deserialize(deserializer: &mut Deserializer) -> Option<Self>;
+
+    // Fails if there are remaining bytes after the value
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut deserializer = Deserializer::new(bytes);
+        let value = Self::deserialize(&mut deserializer)?;
+
+        match deserializer.is_finished() {
+            true => Some(value),
+            false => None,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+// Dummy hash function (32-bit FNV-1a), used in place of a real cryptographic hash.
+pub struct Hasher {
+    state: u32,
+}
+
+impl Hasher {
+    pub fn new() -> Self {
+        Self { state: 0x811C9DC5 }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u32;
+            self.state = self.state.wrapping_mul(0x01000193);
+        }
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub fn finish(&self) -> u32 {
+        self.state
+    }
+}
@@ -1,2 +1,6 @@
 pub mod key_registry;
-pub mod counter;
\ No newline at end of file
+pub mod counter;
+pub mod clock;
+pub mod hasher;
+pub mod serializer;
+pub mod deserializer;
\ No newline at end of file
@@ -0,0 +1,52 @@
+// Canonical binary encoding of the chain data structures: integers are little-endian
+// and lists are prefixed by their number of elements (as a u32).
+pub struct Serializer {
+    bytes: Vec<u8>,
+}
+
+impl Serializer {
+    pub fn new() -> Self {
+        Self { bytes: vec![] }
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_u32(bytes.len() as u32);
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn write_list<T: Serializable>(&mut self, list: &[T]) {
+        self.write_u32(list.len() as u32);
+
+        for item in list {
+            item.serialize(self);
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub trait Serializable {
+    fn serialize(&self, serializer: &mut Serializer);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut serializer = Serializer::new();
+
+        self.serialize(&mut serializer);
+        serializer.into_bytes()
+    }
+
+    // Size in bytes of the canonical encoding
+    fn get_size(&self) -> usize {
+        self.to_bytes().len()
+    }
+}